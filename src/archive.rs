@@ -0,0 +1,140 @@
+use crate::{
+    file_timestamp, is_companion_file, is_temp_file, split_part_number, Args, LATEST_LINK_STEM,
+};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Player data read back from a previously saved CSV file. Values are looked up by column name,
+/// so files saved by versions with other columns can be read alongside current ones
+#[derive(Debug, Clone)]
+pub struct SavedMatch {
+    /// Names from the header row, including 'Team' and 'Player'
+    pub columns: Vec<String>,
+
+    pub rows: Vec<Vec<String>>,
+
+    /// '#' comment lines, such as the '--embed-version' line
+    pub comments: Vec<String>,
+
+    /// Whether the file has a row of column descriptions, from '--with-descriptions'
+    pub has_descriptions: bool,
+
+    /// Whether the file starts with a byte order mark, from '--bom'
+    pub has_bom: bool,
+}
+
+impl SavedMatch {
+    /// Parses the contents of a saved CSV file, skipping '#' comment lines and any row of
+    /// column descriptions
+    pub fn parse(contents: &str) -> SavedMatch {
+        let has_bom = contents.starts_with(crate::BOM);
+        let contents = contents.trim_start_matches(crate::BOM);
+        let comments = contents
+            .lines()
+            .filter(|l| l.starts_with('#'))
+            .map(str::to_string)
+            .collect();
+        let mut lines = contents
+            .lines()
+            .filter(|l| !l.starts_with('#') && !l.is_empty());
+        // Spaces around header names, e.g. after editing in a spreadsheet, don't change them
+        let columns: Vec<String> = lines
+            .next()
+            .map(split_csv_line)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|c| c.trim().to_string())
+            .collect();
+        let descriptions: Vec<&str> = columns
+            .iter()
+            .map(|c| crate::column_description(c))
+            .collect();
+        let mut has_descriptions = false;
+        let rows = lines
+            .map(split_csv_line)
+            .filter(|row| {
+                let is_descriptions = *row == descriptions;
+                has_descriptions |= is_descriptions;
+                !is_descriptions
+            })
+            .collect();
+
+        SavedMatch {
+            columns,
+            rows,
+            comments,
+            has_descriptions,
+            has_bom,
+        }
+    }
+
+    /// Reads a saved CSV file, returning why it is not a valid match if it cannot be used
+    pub fn read(path: &Path) -> Result<SavedMatch, String> {
+        let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let saved = SavedMatch::parse(&contents);
+        if !saved.columns.iter().any(|c| c == "Team")
+            || !saved.columns.iter().any(|c| c == "Player")
+        {
+            return Err("missing a 'Team' or 'Player' column".to_string());
+        }
+        if let Some(row) = saved.rows.iter().find(|r| r.len() != saved.columns.len()) {
+            return Err(format!(
+                "row has {} values but the header has {}",
+                row.len(),
+                saved.columns.len()
+            ));
+        }
+        Ok(saved)
+    }
+
+    /// Gets the value of the named column for a row
+    pub fn value<'a>(&self, row: &'a [String], column: &str) -> Option<&'a str> {
+        let index = self.columns.iter().position(|c| c == column)?;
+        row.get(index).map(String::as_str)
+    }
+}
+
+/// Lists the CSV files saved in the output directory, oldest match first, leaving out the
+/// temporary file, the '--latest-symlink' link and '--split-teams' or '--events' files
+pub fn saved_csv_files(args: &Args, output_dir_path: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = fs::read_dir(output_dir_path)?
+        .flatten()
+        .map(|de| de.path())
+        .filter(|p| {
+            p.is_file()
+                && p.extension().is_some_and(|e| e == "csv")
+                && !is_temp_file(args, p)
+                && p.file_stem().is_some_and(|s| s != LATEST_LINK_STEM)
+                && !is_companion_file(p)
+        })
+        .collect();
+    files.sort_by_cached_key(|p| {
+        let part = p.file_stem().and_then(|s| split_part_number(s.to_str()?));
+        (file_timestamp(p), part.map(|(_, part)| part))
+    });
+    Ok(files)
+}
+
+/// Splits a CSV line into fields, removing the quotes around quoted fields
+pub fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+
+    fields
+}
@@ -0,0 +1,34 @@
+use clap::ValueEnum;
+use owo_colors::{AnsiColors, OwoColorize};
+use std::io::IsTerminal;
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether console output should be colored, where 'auto' respects NO_COLOR and only
+    /// colors a terminal
+    pub fn enabled(&self) -> bool {
+        match self {
+            ColorChoice::Auto => {
+                std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+                    && std::io::stdout().is_terminal()
+            }
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+/// Colors text for the console if `enabled`
+pub fn paint(enabled: bool, text: &str, color: AnsiColors) -> String {
+    if enabled {
+        text.color(color).to_string()
+    } else {
+        text.to_string()
+    }
+}
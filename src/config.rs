@@ -0,0 +1,193 @@
+use directories::{ProjectDirs, UserDirs};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::error::Error;
+use std::fs;
+use std::hash::BuildHasher;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Steam app ID of Hunt: Showdown
+const HUNT_APP_ID: &str = "594650";
+
+/// Settings persisted between runs, used when the corresponding argument is not given
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Config {
+    /// Path of 'attributes.xml'
+    pub input: Option<String>,
+}
+
+impl Config {
+    /// Path of the config file, e.g. '~/.config/hunt-summary-extractor/config.toml'
+    pub fn path() -> Option<PathBuf> {
+        ProjectDirs::from("", "", env!("CARGO_PKG_NAME"))
+            .map(|pd| pd.config_dir().join("config.toml"))
+    }
+
+    /// Loads the config file, or the default config if there is none
+    pub fn load() -> Result<Config, Box<dyn Error>> {
+        match Config::path() {
+            Some(path) if path.is_file() => Ok(toml::from_str(&fs::read_to_string(path)?)?),
+            _ => Ok(Config::default()),
+        }
+    }
+
+    pub fn save(&self) -> Result<PathBuf, Box<dyn Error>> {
+        let path = Config::path().ok_or("Could not determine config directory")?;
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::write(&path, toml::to_string(self)?)?;
+        Ok(path)
+    }
+}
+
+/// Path of the salt '--anonymize' hashes with, kept next to the config file
+fn salt_path() -> Option<PathBuf> {
+    Config::path().map(|path| path.with_file_name("anonymize-salt"))
+}
+
+/// Loads the salt '--anonymize' hashes with, creating it the first time so that the same
+/// hunter gets the same token in every run and duplicate matches are still recognized
+pub fn anonymize_salt() -> Result<u64, Box<dyn Error>> {
+    let path = salt_path().ok_or("Could not determine config directory")?;
+    if let Ok(salt) = fs::read_to_string(&path) {
+        return Ok(u64::from_str_radix(salt.trim(), 16)?);
+    }
+    let salt = RandomState::new().hash_one(SystemTime::now());
+    fs::create_dir_all(path.parent().unwrap())?;
+    fs::write(&path, format!("{salt:016x}"))?;
+    Ok(salt)
+}
+
+/// Library folders where Steam commonly installs games
+fn steam_library_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![
+        PathBuf::from(r"C:\Program Files (x86)\Steam"),
+        PathBuf::from(r"C:\Program Files\Steam"),
+    ];
+    for drive in 'C'..='H' {
+        dirs.push(PathBuf::from(format!(r"{drive}:\SteamLibrary")));
+        dirs.push(PathBuf::from(format!(r"{drive}:\Steam")));
+    }
+    if let Some(ud) = UserDirs::new() {
+        let home = ud.home_dir();
+        dirs.push(home.join(".steam").join("steam"));
+        dirs.push(home.join(".local").join("share").join("Steam"));
+        dirs.push(home.join("Library/Application Support/Steam"));
+    }
+    dirs
+}
+
+/// Finds every existing 'attributes.xml' in the common Steam library folders
+pub fn find_attributes_files() -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    for library in steam_library_dirs() {
+        for game_dir in ["Hunt Showdown", "Hunt Showdown 1896"] {
+            let profiles = library
+                .join("steamapps")
+                .join("common")
+                .join(game_dir)
+                .join("user")
+                .join("profiles");
+            if let Ok(entries) = fs::read_dir(&profiles) {
+                for entry in entries.flatten() {
+                    let path = entry.path().join("attributes.xml");
+                    if path.is_file() {
+                        found.push(path);
+                    }
+                }
+            }
+        }
+    }
+    found
+}
+
+/// Adds every 'attributes*.xml' file in `dir` and its subdirectories to `found`
+fn find_attributes_in(dir: &Path, found: &mut Vec<PathBuf>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for path in entries.flatten().map(|de| de.path()) {
+        let name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_lowercase();
+        if path.is_dir() {
+            find_attributes_in(&path, found);
+        } else if name.starts_with("attributes") && name.ends_with(".xml") {
+            found.push(path);
+        }
+    }
+}
+
+/// Finds every 'attributes.xml' cached by Steam Cloud under each Steam user's folder for Hunt
+pub fn find_steam_cloud_attributes_files() -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    for library in steam_library_dirs() {
+        if let Ok(users) = fs::read_dir(library.join("userdata")) {
+            for user in users.flatten() {
+                find_attributes_in(&user.path().join(HUNT_APP_ID), &mut found);
+            }
+        }
+    }
+    found.sort();
+    found
+}
+
+/// Prints a message and reads the answer, failing if input has ended rather than answering
+/// with an empty line forever
+fn prompt(message: &str) -> io::Result<String> {
+    print!("{message}");
+    io::stdout().flush()?;
+    let mut line = String::new();
+    if io::stdin().lock().read_line(&mut line)? == 0 {
+        println!();
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "setup cancelled, input ended",
+        ));
+    }
+    Ok(line.trim().to_string())
+}
+
+/// Interactively asks the user for the location of 'attributes.xml' and saves it to the
+/// config file
+pub fn run_setup() -> Result<(), Box<dyn Error>> {
+    let candidates = find_attributes_files();
+    if candidates.is_empty() {
+        println!("Could not find 'attributes.xml' in any common Steam library folder.");
+    } else {
+        println!("Found the following 'attributes.xml' files:");
+        for (i, path) in candidates.iter().enumerate() {
+            println!("  {}) {}", i + 1, path.to_string_lossy());
+        }
+    }
+
+    let input = loop {
+        let answer =
+            prompt("Enter a number from the list, or the full path of 'attributes.xml': ")?;
+        let path = match answer.parse::<usize>() {
+            Ok(n) if (1..=candidates.len()).contains(&n) => candidates[n - 1].clone(),
+            _ => PathBuf::from(&answer),
+        };
+
+        if !path.is_file() {
+            println!("'{}' does not exist.", path.to_string_lossy());
+            continue;
+        }
+
+        let confirm = prompt(&format!("Use '{}'? [Y/n] ", path.to_string_lossy()))?;
+        if confirm.is_empty() || confirm.eq_ignore_ascii_case("y") {
+            break path;
+        }
+    };
+
+    let mut config = Config::load()?;
+    config.input = Some(input.to_string_lossy().into_owned());
+    let path = config.save()?;
+    println!("Saved config: '{}'", path.to_string_lossy());
+
+    Ok(())
+}
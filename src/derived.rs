@@ -0,0 +1,292 @@
+use crate::{Args, Match, PlayerRow};
+
+/// Parses a counter or flag attribute, treating 'true' as 1 and anything unparsable as 0
+pub fn number(value: Option<&str>) -> i64 {
+    match value {
+        Some("true") => 1,
+        Some(v) => v.parse().unwrap_or(0),
+        None => 0,
+    }
+}
+
+pub fn is_mine(args: &Args, match_data: &Match, row: &PlayerRow) -> bool {
+    args.my_profile_id.is_some()
+        && match_data.value(row, "profileid") == args.my_profile_id.as_deref()
+}
+
+/// How the match ended for your own hunter, given a lookup of that hunter's column values
+pub fn outcome<'a>(value: impl Fn(&str) -> Option<&'a str>) -> &'static str {
+    let extracted = number(value("teamextraction")) > 0;
+    let bounty = if extracted {
+        number(value("bountyextracted")) > 0
+    } else {
+        number(value("bountypickedup")) > 0
+    };
+
+    match (extracted, bounty) {
+        (true, true) => "extracted_with_bounty",
+        (true, false) => "extracted_no_bounty",
+        (false, true) => "died_with_bounty",
+        (false, false) => "died",
+    }
+}
+
+/// Position of a player's MMR in the lobby, where 1 is the highest and ties share a rank
+fn mmr_rank(match_data: &Match, row: &PlayerRow) -> String {
+    let mmr = number(match_data.value(row, "mmr"));
+    let higher = match_data
+        .rows
+        .iter()
+        .filter(|other| number(match_data.value(other, "mmr")) > mmr)
+        .count();
+    (higher + 1).to_string()
+}
+
+/// Average MMR of the players in a row's team
+fn team_mmr(match_data: &Match, row: &PlayerRow) -> f64 {
+    let team: Vec<i64> = match_data
+        .rows
+        .iter()
+        .filter(|other| other.team == row.team)
+        .map(|other| number(match_data.value(other, "mmr")))
+        .collect();
+    team.iter().sum::<i64>() as f64 / team.len() as f64
+}
+
+/// Whether you and a hunter downed or killed each other
+fn had_interaction(match_data: &Match, row: &PlayerRow) -> bool {
+    ["downedbyme", "killedbyme", "downedme", "killedme"]
+        .iter()
+        .any(|column| number(match_data.value(row, column)) > 0)
+}
+
+/// Hunt's star ratings and the lowest MMR for each
+const STAR_BRACKETS: &[(&str, i64)] = &[
+    ("1 star", 0),
+    ("2 stars", 2000),
+    ("3 stars", 2300),
+    ("4 stars", 2600),
+    ("5 stars", 2750),
+    ("6 stars", 3000),
+];
+
+/// Parses a '--brackets' entry of the form 'NAME:MIN'
+pub fn parse_bracket(value: &str) -> Result<(String, i64), String> {
+    let (name, min) = value
+        .rsplit_once(':')
+        .ok_or_else(|| format!("expected 'NAME:MIN', got '{value}'"))?;
+    let min = min
+        .parse()
+        .map_err(|_| format!("'{min}' is not a whole number"))?;
+    Ok((name.to_string(), min))
+}
+
+/// The '--brackets', or the star ratings if none were given
+fn brackets(args: &Args) -> Vec<(&str, i64)> {
+    if args.brackets.is_empty() {
+        STAR_BRACKETS.to_vec()
+    } else {
+        args.brackets
+            .iter()
+            .map(|(name, min)| (name.as_str(), *min))
+            .collect()
+    }
+}
+
+/// Name of the highest bracket whose lowest MMR is reached, if any
+fn bracket_of<'a>(brackets: &[(&'a str, i64)], mmr: i64) -> Option<&'a str> {
+    brackets
+        .iter()
+        .filter(|&&(_, min)| mmr >= min)
+        .max_by_key(|&&(_, min)| min)
+        .map(|&(name, _)| name)
+}
+
+/// Name of the highest bracket whose lowest MMR the row's MMR reaches, if any
+fn mmr_bracket(args: &Args, match_data: &Match, row: &PlayerRow) -> String {
+    let mmr = number(match_data.value(row, "mmr"));
+    bracket_of(&brackets(args), mmr)
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// A text histogram of how many players in the lobby are in each MMR bracket, lowest first,
+/// leaving out players without an MMR
+pub fn mmr_histogram(args: &Args, match_data: &Match) -> String {
+    let mut brackets = brackets(args);
+    brackets.sort_by_key(|&(_, min)| min);
+    let mut counts = vec![0; brackets.len()];
+    for row in &match_data.rows {
+        let Some(mmr) = match_data
+            .value(row, "mmr")
+            .and_then(|v| v.parse::<i64>().ok())
+        else {
+            continue;
+        };
+        if let Some(index) = brackets.iter().rposition(|&(_, min)| mmr >= min) {
+            counts[index] += 1;
+        }
+    }
+
+    let width = brackets
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+    let mut histogram = String::from("MMR distribution:");
+    for ((name, _), count) in brackets.iter().zip(counts) {
+        let line = format!("  {name:<width$} | {count:>2} {}", "#".repeat(count));
+        histogram.push_str(&format!("\n{}", line.trim_end()));
+    }
+    histogram
+}
+
+/// Lowest and highest MMR in Hunt, roughly
+const MMR_RANGE: (f64, f64) = (0.0, 6000.0);
+
+/// A player's MMR scaled from `MMR_RANGE` to between 0 and 1
+fn mmr_normalized(match_data: &Match, row: &PlayerRow) -> f64 {
+    let (min, max) = MMR_RANGE;
+    let mmr = number(match_data.value(row, "mmr")) as f64;
+    ((mmr - min) / (max - min)).clamp(0.0, 1.0)
+}
+
+/// Your kills and your team's kills per time you were downed or killed, given your own row.
+/// Each is counted on the rows of the hunters involved, as yours only relates you to yourself
+pub fn my_kda(match_data: &Match, row: &PlayerRow) -> f64 {
+    let total = |column| -> i64 {
+        match_data
+            .rows
+            .iter()
+            .filter(|other| (other.team, other.player) != (row.team, row.player))
+            .map(|other| number(match_data.value(other, column)))
+            .sum()
+    };
+    let kills = total("killedbyme") + total("killedbyteammate");
+    let deaths = total("killedme") + total("downedme");
+    kills as f64 / deaths.max(1) as f64
+}
+
+/// Formats a computed value to '--precision' decimal places
+pub fn float(args: &Args, value: f64) -> String {
+    format!("{value:.*}", args.precision)
+}
+
+/// Raw columns each derived column is computed from
+const DERIVED_INPUTS: &[(&str, &[&str])] = &[
+    (
+        "outcome",
+        &[
+            "profileid",
+            "teamextraction",
+            "bountyextracted",
+            "bountypickedup",
+        ],
+    ),
+    ("mmr_rank", &["mmr"]),
+    (
+        "had_interaction",
+        &["downedbyme", "killedbyme", "downedme", "killedme"],
+    ),
+    ("mmr_bracket", &["mmr"]),
+    ("mmr_normalized", &["mmr"]),
+    ("team_mmr", &["mmr"]),
+];
+
+/// Recomputes the derived columns enabled by `args` for a previously saved match, replacing
+/// them where it already has them and appending them otherwise. A value is left blank if the
+/// row lacks a raw value it is computed from, and other columns are kept as they are
+pub fn recompute_derived_columns(args: &Args, match_data: &mut Match) {
+    // Computed on a copy without any derived columns, so ones computed from others never use
+    // stale values
+    let mut recomputed = match_data.clone();
+    let derived: Vec<usize> = recomputed
+        .columns
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| DERIVED_INPUTS.iter().any(|(name, _)| c == name))
+        .map(|(i, _)| i)
+        .collect();
+    for &index in derived.iter().rev() {
+        recomputed.columns.remove(index);
+        for row in recomputed.rows.iter_mut() {
+            row.values.remove(index);
+        }
+    }
+
+    let first_derived = recomputed.columns.len();
+    add_derived_columns(args, &mut recomputed);
+
+    for index in first_derived..recomputed.columns.len() {
+        let column = &recomputed.columns[index];
+        let inputs = DERIVED_INPUTS
+            .iter()
+            .find(|(name, _)| name == column)
+            .map_or(&[][..], |(_, inputs)| *inputs);
+        let values: Vec<String> = recomputed
+            .rows
+            .iter()
+            .map(|row| {
+                let missing = inputs
+                    .iter()
+                    .any(|input| recomputed.value(row, input).is_none_or(str::is_empty));
+                if missing {
+                    String::new()
+                } else {
+                    row.values[index].clone()
+                }
+            })
+            .collect();
+
+        match match_data.columns.iter().position(|c| c == column) {
+            Some(existing) => {
+                for (row, value) in match_data.rows.iter_mut().zip(values) {
+                    row.values[existing] = value;
+                }
+            }
+            None => {
+                match_data.columns.push(column.clone());
+                for (row, value) in match_data.rows.iter_mut().zip(values) {
+                    row.values.push(value);
+                }
+            }
+        }
+    }
+}
+
+/// Appends the derived columns enabled by `args`
+pub fn add_derived_columns(args: &Args, match_data: &mut Match) {
+    if args.outcome {
+        match_data.push_column("outcome", |m, row| {
+            if is_mine(args, m, row) {
+                outcome(|column| m.value(row, column)).to_string()
+            } else {
+                String::new()
+            }
+        });
+    }
+
+    if args.mmr_rank {
+        match_data.push_column("mmr_rank", mmr_rank);
+    }
+
+    if args.had_interaction {
+        match_data.push_column("had_interaction", |m, row| {
+            had_interaction(m, row).to_string()
+        });
+    }
+
+    if args.mmr_bracket {
+        match_data.push_column("mmr_bracket", |m, row| mmr_bracket(args, m, row));
+    }
+
+    if args.mmr_normalized {
+        match_data.push_column("mmr_normalized", |m, row| {
+            float(args, mmr_normalized(m, row))
+        });
+    }
+
+    if args.team_mmr {
+        match_data.push_column("team_mmr", |m, row| float(args, team_mmr(m, row)));
+    }
+}
@@ -0,0 +1,178 @@
+use crate::archive::SavedMatch;
+use crate::color::paint;
+use owo_colors::AnsiColors;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Player details used to compare two matches, keyed by profile ID
+struct DiffPlayer<'a> {
+    team: &'a str,
+    name: &'a str,
+    mmr: &'a str,
+}
+
+fn players(match_data: &SavedMatch) -> BTreeMap<&str, DiffPlayer<'_>> {
+    match_data
+        .rows
+        .iter()
+        .filter_map(|row| {
+            Some((
+                match_data.value(row, "profileid")?,
+                DiffPlayer {
+                    team: match_data.value(row, "Team").unwrap_or_default(),
+                    name: match_data.value(row, "blood_line_name").unwrap_or_default(),
+                    mmr: match_data.value(row, "mmr").unwrap_or_default(),
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Rows keyed by profile ID
+fn rows_by_id(match_data: &SavedMatch) -> BTreeMap<&str, &[String]> {
+    match_data
+        .rows
+        .iter()
+        .filter_map(|row| Some((match_data.value(row, "profileid")?, row.as_slice())))
+        .collect()
+}
+
+/// Number of players in each team, in order of team number
+fn team_sizes(match_data: &SavedMatch) -> Vec<usize> {
+    let mut sizes: BTreeMap<u32, usize> = BTreeMap::new();
+    for row in &match_data.rows {
+        if let Some(team) = match_data.value(row, "Team").and_then(|t| t.parse().ok()) {
+            *sizes.entry(team).or_default() += 1;
+        }
+    }
+    sizes.into_values().collect()
+}
+
+/// Describes how the lobby of `new` differs from that of `old`, optionally colored for the
+/// console
+pub fn diff_matches(old: &SavedMatch, new: &SavedMatch, color: bool) -> String {
+    let mut out = diff_lobby(old, new, color);
+    if out.is_empty() {
+        out.push_str("No differences\n");
+    }
+    out
+}
+
+/// Describes the lobby changes and also every other changed value, for '--compare'
+pub fn compare_matches(old: &SavedMatch, new: &SavedMatch, color: bool) -> String {
+    let mut out = diff_lobby(old, new, color);
+    out.push_str(&diff_fields(old, new, color));
+    if out.is_empty() {
+        out.push_str("No differences\n");
+    }
+    out
+}
+
+/// Lines for the team sizes, players and MMR that differ between two matches
+fn diff_lobby(old: &SavedMatch, new: &SavedMatch, color: bool) -> String {
+    let mut out = String::new();
+    let old_players = players(old);
+    let new_players = players(new);
+
+    let old_sizes = team_sizes(old);
+    let new_sizes = team_sizes(new);
+    if old_sizes != new_sizes {
+        writeln!(out, "Team sizes: {old_sizes:?} -> {new_sizes:?}").unwrap();
+    }
+
+    for (id, p) in new_players.iter() {
+        match old_players.get(id) {
+            None => writeln!(
+                out,
+                "{}",
+                paint(
+                    color,
+                    &format!("+ {} ({id}), team {}, MMR {}", p.name, p.team, p.mmr),
+                    AnsiColors::Green
+                )
+            )
+            .unwrap(),
+            Some(old_p) if old_p.mmr != p.mmr => {
+                let delta = match (old_p.mmr.parse::<i64>(), p.mmr.parse::<i64>()) {
+                    (Ok(o), Ok(n)) => {
+                        format!(
+                            " {}",
+                            paint(color, &format!("({:+})", n - o), AnsiColors::Yellow)
+                        )
+                    }
+                    _ => String::new(),
+                };
+                writeln!(
+                    out,
+                    "~ {} ({id}), MMR {} -> {}{delta}",
+                    p.name, old_p.mmr, p.mmr
+                )
+                .unwrap();
+            }
+            _ => {}
+        }
+    }
+
+    for (id, p) in old_players.iter() {
+        if !new_players.contains_key(id) {
+            writeln!(
+                out,
+                "{}",
+                paint(
+                    color,
+                    &format!("- {} ({id}), team {}, MMR {}", p.name, p.team, p.mmr),
+                    AnsiColors::Red
+                )
+            )
+            .unwrap();
+        }
+    }
+
+    out
+}
+
+/// Describes which columns each match has that the other lacks, and every value other than
+/// MMR that changed for players in both
+fn diff_fields(old: &SavedMatch, new: &SavedMatch, color: bool) -> String {
+    let mut out = String::new();
+
+    for (columns, other, sign, ansi) in [
+        (&old.columns, &new.columns, '-', AnsiColors::Red),
+        (&new.columns, &old.columns, '+', AnsiColors::Green),
+    ] {
+        let only: Vec<&str> = columns
+            .iter()
+            .filter(|c| !other.contains(c))
+            .map(String::as_str)
+            .collect();
+        if !only.is_empty() {
+            let line = format!("{sign} columns: {}", only.join(", "));
+            writeln!(out, "{}", paint(color, &line, ansi)).unwrap();
+        }
+    }
+
+    let new_rows = rows_by_id(new);
+    for (id, old_row) in rows_by_id(old) {
+        let Some(new_row) = new_rows.get(id) else {
+            continue;
+        };
+        let name = new.value(new_row, "blood_line_name").unwrap_or_default();
+        for column in old.columns.iter().filter(|c| *c != "mmr") {
+            let (Some(old_value), Some(new_value)) =
+                (old.value(old_row, column), new.value(new_row, column))
+            else {
+                continue;
+            };
+            if old_value != new_value {
+                writeln!(
+                    out,
+                    "~ {name} ({id}), {column} {old_value} -> {}",
+                    paint(color, new_value, AnsiColors::Yellow)
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    out
+}
@@ -0,0 +1,46 @@
+use std::io;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Failures while extracting player data from 'attributes.xml'
+#[derive(Error, Debug)]
+pub enum ExtractError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    #[error("Could not parse 'attributes.xml': {0}")]
+    XmlParse(#[from] quick_xml::DeError),
+
+    #[error("Could not read zip archive: {0}")]
+    Archive(#[from] zip::result::ZipError),
+
+    #[error("Missing attribute '{key}'")]
+    MissingAttribute { key: String },
+
+    #[error("Attribute '{key}' has invalid team size or count '{value}'")]
+    InvalidTeamCount { key: String, value: String },
+
+    #[error("Found stale 'MissionBagPlayer_{team}_{player}_*' attributes, but team {team} only has {team_size} players")]
+    StaleAttributes {
+        team: u32,
+        player: u32,
+        team_size: u32,
+    },
+
+    #[error(
+        "Found stale 'MissionBagPlayer_{team}_*' attributes, but there are only {num_teams} teams"
+    )]
+    StaleTeam { team: u32, num_teams: u32 },
+
+    #[error("Only {found} of the {declared} players declared by the teams have a profile ID")]
+    MissingPlayers { found: u32, declared: u32 },
+
+    #[error("Profile ID '{id}' is in more than one team: {teams}")]
+    DuplicateProfileId { id: String, teams: String },
+
+    #[error("Output file '{}' already exists", .path.to_string_lossy())]
+    OutputExists { path: PathBuf },
+
+    #[error("'--exec' command '{command}' failed: {reason}")]
+    Exec { command: String, reason: String },
+}
@@ -0,0 +1,60 @@
+use crate::error::ExtractError;
+use crate::Attributes;
+use quick_xml::de::from_str;
+use std::collections::{BTreeMap, HashMap};
+
+/// Fields of each 'MissionBagEntry_<n>_*' attribute written to the events file, in column order
+const EVENT_FIELDS: &[&str] = &[
+    "category",
+    "descriptorName",
+    "descriptorType",
+    "amount",
+    "reward",
+    "uiName",
+];
+
+/// Renders the match's event feed from its 'MissionBagEntry_*' attributes as CSV, one row per
+/// entry
+pub fn render_events(xml: &str) -> Result<String, ExtractError> {
+    let attributes: Attributes = from_str(xml)?;
+
+    // Entries beyond the count are left over from earlier matches
+    let num_entries: Option<u32> = attributes
+        .items
+        .iter()
+        .find(|item| item.name == "MissionBagNumEntries")
+        .and_then(|item| item.value.parse().ok());
+
+    let mut entries: BTreeMap<u32, HashMap<&str, &str>> = BTreeMap::new();
+    for item in &attributes.items {
+        let Some((entry, field)) = item
+            .name
+            .strip_prefix("MissionBagEntry_")
+            .and_then(|rest| rest.split_once('_'))
+        else {
+            continue;
+        };
+        let Ok(entry) = entry.parse::<u32>() else {
+            continue;
+        };
+        if num_entries.is_none_or(|count| entry < count) {
+            entries
+                .entry(entry)
+                .or_default()
+                .insert(field, item.value.as_str());
+        }
+    }
+
+    let mut csv = format!("Entry,{}", EVENT_FIELDS.join(","));
+    for (entry, fields) in &entries {
+        csv.push_str(&format!("\n{entry}"));
+        for field in EVENT_FIELDS {
+            match fields.get(field).copied().unwrap_or_default() {
+                v if v.contains(',') => csv.push_str(&format!(",\"{v}\"")),
+                v => csv.push_str(&format!(",{v}")),
+            }
+        }
+    }
+
+    Ok(csv)
+}
@@ -0,0 +1,48 @@
+use crate::{latest_output_file, temp_file_name, Args};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// Checks that the input can be read, that the output directory can be written to and, with
+/// '--max-staleness-mins', that the latest match was saved recently enough, returning the
+/// first problem found
+pub fn health_check(args: &Args, output_dir_path: &Path) -> Result<(), Box<dyn Error>> {
+    if args.input != "-" {
+        fs::File::open(&args.input)
+            .map_err(|e| format!("Unhealthy: can't read input '{}': {e}", args.input))?;
+    }
+
+    let probe = output_dir_path.join(temp_file_name(args));
+    fs::create_dir_all(output_dir_path)
+        .and_then(|_| fs::write(&probe, ""))
+        .and_then(|_| fs::remove_file(&probe))
+        .map_err(|e| {
+            format!(
+                "Unhealthy: can't write to output directory '{}': {e}",
+                output_dir_path.to_string_lossy()
+            )
+        })?;
+
+    if let Some(minutes) = args.max_staleness_mins {
+        let extension = args.format[0].extension();
+        let latest = latest_output_file(args, output_dir_path, extension)?
+            .ok_or("Unhealthy: no match has been saved yet")?;
+        let age = fs::metadata(&latest)?
+            .modified()?
+            .elapsed()
+            .unwrap_or_default();
+        if age > Duration::from_secs(minutes * 60) {
+            return Err(format!(
+                "Unhealthy: the latest match '{}' was saved {} minutes ago, more than \
+                '--max-staleness-mins' {minutes}",
+                latest.to_string_lossy(),
+                age.as_secs() / 60
+            )
+            .into());
+        }
+    }
+
+    println!("Healthy");
+    Ok(())
+}
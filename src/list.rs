@@ -0,0 +1,49 @@
+use crate::archive::{saved_csv_files, SavedMatch};
+use crate::derived::outcome;
+use crate::Args;
+use std::collections::BTreeSet;
+use std::io;
+use std::path::Path;
+
+/// Prints one line per CSV file saved in the output directory, with its team and player
+/// counts and, if '--my-profile-id' is set, how the match ended
+pub fn list_matches(args: &Args, output_dir_path: &Path) -> io::Result<()> {
+    for path in saved_csv_files(args, output_dir_path)? {
+        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+        let saved = match SavedMatch::read(&path) {
+            Ok(saved) => saved,
+            Err(reason) => {
+                println!("{name}  could not be read: {reason}");
+                continue;
+            }
+        };
+
+        let teams: BTreeSet<&str> = saved
+            .rows
+            .iter()
+            .filter_map(|row| saved.value(row, "Team"))
+            .collect();
+        let mut line = format!(
+            "{name}  {} teams  {} players",
+            teams.len(),
+            saved.rows.len()
+        );
+
+        if let Some(id) = &args.my_profile_id {
+            let mine = saved
+                .rows
+                .iter()
+                .find(|row| saved.value(row, "profileid") == Some(id.as_str()));
+            match mine {
+                Some(row) => {
+                    line.push_str(&format!("  {}", outcome(|column| saved.value(row, column))))
+                }
+                None => line.push_str("  not in match"),
+            }
+        }
+
+        println!("{line}");
+    }
+
+    Ok(())
+}
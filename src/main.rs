@@ -1,31 +1,93 @@
+/// Prints a line of console output while extracting, to stderr instead with '--json-result' so
+/// that stdout holds only the result. Defined before the modules so that they can use it
+macro_rules! console {
+    ($($arg:tt)*) => {
+        if $crate::CONSOLE_TO_STDERR.load(std::sync::atomic::Ordering::Relaxed) {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
+mod archive;
+mod color;
+mod config;
+mod derived;
+mod diff;
+mod error;
+mod events;
+mod health;
+mod list;
+mod merge;
+mod progress;
+mod recompute;
+mod replay;
+mod sessions;
+mod settings;
+mod sink;
+mod sse;
+mod stats;
+mod timings;
+
+use archive::SavedMatch;
 use chrono::prelude::*;
-use clap::Parser;
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser, ValueEnum};
+use color::{paint, ColorChoice};
+use config::Config;
+use diff::diff_matches;
 use directories::UserDirs;
-use notify::RecursiveMode;
-use notify_debouncer_mini::new_debouncer;
+use encoding_rs::Encoding;
+use error::ExtractError;
+#[cfg(target_os = "macos")]
+use notify::FsEventWatcher;
+#[cfg(target_os = "linux")]
+use notify::INotifyWatcher;
+use notify::{PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
+use notify_debouncer_mini::{new_debouncer_opt, DebounceEventResult};
+use owo_colors::AnsiColors;
 use quick_xml::de::from_str;
-use serde::Deserialize;
-use std::collections::HashMap;
+use quick_xml::escape::escape;
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::json;
+use std::any::Any;
+use std::borrow::Cow;
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::error::Error;
 use std::fs;
-use std::io::{BufWriter, Write};
+use std::hash::BuildHasher;
+use std::io::{ErrorKind, Read, Write};
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::process::{Command, ExitCode, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{RecvTimeoutError, Sender};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
 
 /// Extracts Hunt: Showdown player match data from 'attributes.xml' into a CSV file
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// Path of 'attributes.xml'
+    /// Path of 'attributes.xml', '-' to read from stdin, or a zip archive to process every
+    /// 'attributes.xml' inside
     #[arg(
         short,
         long,
-        default_value = r"C:\Program Files (x86)\Steam\steamapps\common\Hunt Showdown\user\profiles\default\attributes.xml"
+        env = "HUNT_ATTRIBUTES_PATH",
+        default_value = DEFAULT_INPUT
     )]
     input: String,
 
+    /// Name of the game profile whose 'attributes.xml' to read from the default path, unless
+    /// '--input' is given
+    #[arg(long)]
+    profile: Option<String>,
+
     /// Path of output directory [default: ~/Documents/Hunt/MatchData]
-    #[arg(short, long)]
+    #[arg(short, long, env = "HUNT_OUTPUT_DIR")]
     output_dir: Option<String>,
 
     /// Disable continuous mode, checking only once for file modification
@@ -36,9 +98,505 @@ struct Args {
     #[arg(short, long)]
     zero_based: bool,
 
-    /// Filename for temporary CSV file
-    #[arg(long, default_value = "TEMP.CSV")]
-    temp_file: String,
+    /// Filename for temporary output file [default: TEMP.<process ID>.CSV, so that instances
+    /// sharing an output directory don't collide]
+    #[arg(long)]
+    temp_file: Option<String>,
+
+    /// Character encoding of 'attributes.xml', a BOM takes precedence if present
+    #[arg(long, default_value = "utf-8")]
+    encoding: String,
+
+    /// Write the extractor version as a '#' comment line at the top of the CSV
+    #[arg(long)]
+    embed_version: bool,
+
+    /// Start CSV files with a UTF-8 byte order mark, so that Excel reads non-ASCII hunter
+    /// names correctly
+    #[arg(long)]
+    bom: bool,
+
+    /// End CSV files with a newline, as many Unix tools expect
+    #[arg(long)]
+    trailing_newline: bool,
+
+    /// In continuous mode, also process the existing file once on startup
+    #[arg(long)]
+    watch_existing: bool,
+
+    /// Text written in place of empty attribute values [default: empty]
+    #[arg(long)]
+    na_string: Option<String>,
+
+    /// Fail if player attributes exist beyond the declared teams or a team's declared number of
+    /// players, if fewer players have a profile ID than the teams declare, or if a profile ID is
+    /// in two teams
+    #[arg(long)]
+    strict: bool,
+
+    /// Output formats to write for each match, the first is used to detect duplicates
+    #[arg(short, long, value_enum, value_delimiter = ',', default_value = "csv")]
+    format: Vec<Format>,
+
+    /// Interactively locate 'attributes.xml' and save it to the config file
+    #[arg(long)]
+    setup: bool,
+
+    /// Load settings from a JSON file written by '--export-config', for those not given on
+    /// the command line or through an environment variable
+    #[arg(long, value_name = "FILE")]
+    config: Option<String>,
+
+    /// Write the settings given on the command line or through environment variables to a JSON
+    /// file for sharing, then exit
+    #[arg(long, value_name = "FILE")]
+    export_config: Option<String>,
+
+    /// Add a 'region' column with the server region of the match
+    #[arg(long)]
+    region: bool,
+
+    /// Name of the attribute holding the server region of the match
+    #[arg(long, default_value = "MissionBagRegion")]
+    region_attr: String,
+
+    /// Add a 'boss_<name>' column for each boss, e.g. 'boss_butcher', saying whether it was in
+    /// the match
+    #[arg(long)]
+    bosses: bool,
+
+    /// Prefix of the attributes saying whether each boss was in the match, followed by the
+    /// boss's index
+    #[arg(long, default_value = "MissionBagBoss_")]
+    boss_attr_prefix: String,
+
+    /// Print how a new match differs from the previously saved CSV file
+    #[arg(long)]
+    diff_latest: bool,
+
+    /// Profile ID of your own hunter
+    #[arg(long)]
+    my_profile_id: Option<String>,
+
+    /// Replace hunter names and profile IDs with tokens, except for '--my-profile-id'. Tokens are
+    /// hashed with a salt kept next to the config file, so they are the same in every run
+    #[arg(long)]
+    anonymize: bool,
+
+    /// Add an 'outcome' column describing how the match ended for '--my-profile-id'
+    #[arg(long, requires = "my_profile_id")]
+    outcome: bool,
+
+    /// Always save a new file, even if it matches the latest saved file
+    #[arg(long)]
+    no_dedup: bool,
+
+    /// What must match the latest saved file for a match to be a duplicate: its full contents,
+    /// or only the enemy players' profile IDs, names and teams, which stay the same when your own
+    /// MMR updates after the match. Lobbies are compared with the latest CSV file, so 'lobby'
+    /// requires 'csv' in '--format'
+    #[arg(long, value_enum, default_value = "content")]
+    dedup_key: DedupKey,
+
+    /// Print a one-line summary of each new match instead of its full contents
+    #[arg(long)]
+    brief: bool,
+
+    /// Name of the attribute holding the number of teams
+    #[arg(long, default_value = "MissionBagNumTeams")]
+    team_count_attr: String,
+
+    /// Name of the attribute holding the number of players in a team, where '{team}' is
+    /// replaced by the zero-based team number
+    #[arg(long, default_value = "MissionBagTeam_{team}_numplayers")]
+    team_size_attr: String,
+
+    /// In continuous mode, exit after this many seconds without changes, or 0 to never exit
+    #[arg(long, default_value_t = 0)]
+    watch_timeout: u64,
+
+    /// In continuous mode, exit after saving this many new matches
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    limit: Option<u64>,
+
+    /// Print a JSON Schema describing the JSON output and exit
+    #[arg(long)]
+    emit_schema: bool,
+
+    /// Point a 'latest.<ext>' symlink, or a copy where unavailable, at each new output file
+    #[arg(long)]
+    latest_symlink: bool,
+
+    /// Add columns for any extra proximity attributes, and write proximity values as integers
+    /// in JSON output
+    #[arg(long)]
+    proximity_detail: bool,
+
+    /// URL to POST each new match to as JSON
+    #[arg(long)]
+    post_url: Option<String>,
+
+    /// Directory of matches that failed to POST, retried on startup and after the next
+    /// successful POST [default: <OUTPUT_DIR>/spool, or none with '--no-local']
+    #[arg(long)]
+    spool_dir: Option<String>,
+
+    /// Find the latest existing output file to compare against by modification time, or by the
+    /// timestamp in its filename
+    #[arg(long, value_enum, default_value = "mtime")]
+    latest_by: LatestBy,
+
+    /// When to color console output
+    #[arg(long, value_enum, default_value = "auto")]
+    color: ColorChoice,
+
+    /// Shell command given every attribute as a JSON object on stdin, whose output is saved
+    /// instead of the first '--format'
+    #[arg(long)]
+    exec: Option<String>,
+
+    /// Ignore player attributes left over from a previous match, beyond the current teams and
+    /// team sizes
+    #[arg(long)]
+    clear_stale: bool,
+
+    /// Write a second CSV header row describing each column
+    #[arg(long)]
+    with_descriptions: bool,
+
+    /// In continuous mode, log that the watcher is still running at this interval in seconds,
+    /// or 0 to never log
+    #[arg(long, default_value_t = 0)]
+    heartbeat: u64,
+
+    /// Leave out teams without any players, keeping the other teams' numbers
+    #[arg(long)]
+    compact_empty_teams: bool,
+
+    /// Renumber teams consecutively after leaving out empty teams
+    #[arg(long, requires = "compact_empty_teams")]
+    renumber: bool,
+
+    /// Add an 'mmr_rank' column ranking each hunter's MMR in the lobby, 1 being the highest
+    #[arg(long)]
+    mmr_rank: bool,
+
+    /// Start each CSV row with a 'match_timestamp' column holding the time the match was saved,
+    /// in the same format as output file names
+    #[arg(long)]
+    include_timestamp_column: bool,
+
+    /// Combine every saved CSV file in the output directory into this file, oldest match
+    /// first, then exit
+    #[arg(long, value_name = "OUTPUT_CSV")]
+    merge: Option<String>,
+
+    /// Split matches with more players than this across numbered files, e.g.
+    /// '<timestamp>_1.csv', each with its own header
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    rows_per_file: Option<u64>,
+
+    /// Save each team to its own file, e.g. '<timestamp>_team1.csv', instead of the whole
+    /// match to one
+    #[arg(long, conflicts_with = "rows_per_file")]
+    per_team_files: bool,
+
+    /// Shell command to run after each new match is saved, given the path of the first output
+    /// file in the 'HUNT_MATCH_FILE' environment variable
+    #[arg(long)]
+    on_new_match: Option<String>,
+
+    /// Add a 'team_mmr' column with the average MMR of each hunter's team
+    #[arg(long)]
+    team_mmr: bool,
+
+    /// Number of decimal places for computed columns that are not whole numbers
+    #[arg(long, default_value_t = 2)]
+    precision: usize,
+
+    /// Add an 'mmr_normalized' column with each hunter's MMR scaled from Hunt's MMR range to
+    /// between 0 and 1
+    #[arg(long)]
+    mmr_normalized: bool,
+
+    /// Print the timestamp, team count, player count and, with '--my-profile-id', outcome of
+    /// every saved CSV file, then exit
+    #[arg(long)]
+    list_matches: bool,
+
+    /// Also write your own team to '<timestamp>.mine.csv' and every other team to
+    /// '<timestamp>.enemies.csv'
+    #[arg(long, requires = "my_profile_id")]
+    split_teams: bool,
+
+    /// Leave out your own team's players, keeping the other teams' numbers unless
+    /// '--compact-empty-teams --renumber' is also given
+    #[arg(long, requires = "my_profile_id", conflicts_with = "split_teams")]
+    enemies_only: bool,
+
+    /// In continuous mode, check the input for changes at this interval in seconds instead of
+    /// waiting for file system events, e.g. on network drives where they never arrive
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    poll: Option<u64>,
+
+    /// In continuous mode, milliseconds to wait for changes to 'attributes.xml' to stop before
+    /// reading it
+    #[arg(long, default_value_t = 2000, conflicts_with = "poll")]
+    debounce_ms: u64,
+
+    /// In continuous mode, which file watcher to use: the platform's recommended one, Linux's
+    /// inotify, macOS's FSEvents, or notify's own watcher that rescans the input every second
+    #[arg(long, value_enum, default_value = "auto", conflicts_with = "poll")]
+    watch_backend: WatchBackend,
+
+    /// In continuous mode, milliseconds to wait before reading 'attributes.xml' once more when
+    /// it has no team data, as the team count can be written just after the rest of a match
+    #[arg(long, default_value_t = 0)]
+    no_match_grace_ms: u64,
+
+    /// Add a 'had_interaction' column saying whether each hunter downed or killed you, or was
+    /// downed or killed by you
+    #[arg(long)]
+    had_interaction: bool,
+
+    /// Columns that must have a value for every player
+    #[arg(long, value_delimiter = ',')]
+    require: Vec<String>,
+
+    /// Whether to skip matches missing a '--require' column, or save them alongside a
+    /// '<timestamp>.incomplete' file listing what is missing
+    #[arg(long, value_enum, default_value = "warn")]
+    require_mode: RequireMode,
+
+    /// Also write each new match in the first '--format' to this existing named pipe, or
+    /// append it to this existing file
+    #[arg(long)]
+    pipe: Option<String>,
+
+    /// SQLite database of running totals across every new match, e.g. kills and extractions
+    #[arg(long)]
+    stats_db: Option<String>,
+
+    /// Print the totals and averages in '--stats-db', then exit
+    #[arg(long, requires = "stats_db")]
+    print_stats: bool,
+
+    /// Skip matches with fewer players than this, e.g. custom practice lobbies
+    #[arg(long, default_value_t = 0)]
+    min_players: usize,
+
+    /// In single mode, finish with a line holding a JSON object describing what happened to
+    /// the match, for use by other programs. Everything else printed goes to stderr instead, so
+    /// that the object is all of stdout
+    #[arg(long, requires = "single")]
+    json_result: bool,
+
+    /// Add an 'is_random' column saying whether each hunter's team was matched with random
+    /// teammates rather than invited, and an 'is_partner' column marking your teammates
+    #[arg(long)]
+    team_composition: bool,
+
+    /// Add a 'team_won' column saying whether each hunter's team extracted, which is how Hunt
+    /// matches are won, from whether any of its players extracted unless '--team-won-attr' is
+    /// set
+    #[arg(long)]
+    team_won: bool,
+
+    /// Add a 'seat' column numbering every player in the lobby in order, across teams
+    #[arg(long)]
+    seat: bool,
+
+    /// Name of a team attribute holding whether the team won, for game versions that record
+    /// one, with '{team}' replaced by the team number, e.g. 'MissionBagTeam_{team}_extracted'
+    #[arg(long, requires = "team_won")]
+    team_won_attr: Option<String>,
+
+    /// List the 'attributes.xml' files cached by Steam Cloud, then exit
+    #[arg(long)]
+    backfill_from_steam_cloud: bool,
+
+    /// Extract player data from each file found by '--backfill-from-steam-cloud'
+    #[arg(long, requires = "backfill_from_steam_cloud")]
+    backfill_process: bool,
+
+    /// Add an 'mmr_bracket' column naming each hunter's MMR bracket, by default its star rating
+    #[arg(long)]
+    mmr_bracket: bool,
+
+    /// Custom brackets for '--mmr-bracket' and '--mmr-histogram', as a list of names and the lowest
+    /// MMR in each, e.g. 'Bronze:0,Silver:2000,Gold:2600'
+    #[arg(long, value_delimiter = ',', value_parser = derived::parse_bracket)]
+    brackets: Vec<(String, i64)>,
+
+    /// Print how many players in a new match are in each star rating, or '--brackets' bracket,
+    /// after its player data
+    #[arg(long)]
+    mmr_histogram: bool,
+
+    /// Remove temporary output files left in the output directory by earlier runs that
+    /// crashed, including those of any other instance using the default name that is no longer
+    /// running
+    #[arg(long)]
+    prune_temp_on_start: bool,
+
+    /// Serve each new match as JSON in a Server-Sent Event on this port of localhost
+    #[arg(long)]
+    sse_port: Option<u16>,
+
+    /// CSV file to append the timestamp and your KDA to for each new match, counting your
+    /// team's kills over the times you were downed or killed
+    #[arg(long, requires = "my_profile_id")]
+    kda_ledger: Option<String>,
+
+    /// Print how long each phase of extracting a match took, and in continuous mode how long
+    /// after 'attributes.xml' changed each new match was saved
+    #[arg(long)]
+    timings: bool,
+
+    /// Copy 'attributes.xml' into this directory, named by timestamp, whenever it has no team
+    /// data
+    #[arg(long)]
+    dump_on_empty: Option<String>,
+
+    /// Write each match to exactly this file instead of a timestamped file in the output
+    /// directory, in the format matching its extension, e.g. 'result.json'
+    #[arg(long)]
+    output_file: Option<String>,
+
+    /// Replace negative kill and down counts, which some anonymized opponents have, with 0 or
+    /// with an empty value
+    #[arg(long, value_name = "REPLACEMENT", num_args = 0..=1, default_missing_value = "zero")]
+    clamp_negatives: Option<Clamp>,
+
+    /// Also save the match's event feed of kills and bounty events from its
+    /// 'MissionBagEntry_*' attributes to '<timestamp>.events.csv'
+    #[arg(long)]
+    events: bool,
+
+    /// Send every saved CSV file to '--post-url' and '--sse-port' clients in chronological
+    /// order as if each were a new match, then exit
+    #[arg(long)]
+    replay: bool,
+
+    /// Seconds to wait between matches sent by '--replay'
+    #[arg(long, value_name = "SECONDS", default_value_t = 0, requires = "replay")]
+    replay_delay: u64,
+
+    /// Rewrite every saved CSV file with the derived columns enabled by the other options,
+    /// e.g. '--outcome', computed from its raw columns and its other columns kept, backing each
+    /// up to '<file>.bak' the first time, then exit
+    #[arg(long)]
+    recompute_derived: bool,
+
+    /// Group the saved CSV files into play sessions and write the start, end, number of
+    /// matches and your MMR change of each to this file, then exit
+    #[arg(long, value_name = "SESSIONS_CSV")]
+    sessionize: Option<String>,
+
+    /// Print how the players and values of two saved CSV files differ, then exit
+    #[arg(long, num_args = 2, value_names = ["FILE_A", "FILE_B"])]
+    compare: Vec<String>,
+
+    /// Check that the input is readable and the output directory is writable, exiting with an
+    /// error saying why if not, for service monitoring
+    #[arg(long)]
+    health_check: bool,
+
+    /// Also fail '--health-check' if the latest match was saved more than this many minutes ago
+    #[arg(long, requires = "health_check")]
+    max_staleness_mins: Option<u64>,
+
+    /// Only send new matches to '--post-url' and '--sse-port' clients, without saving any
+    /// files, remembering the last match in memory to skip duplicates
+    #[arg(long, conflicts_with = "output_file")]
+    no_local: bool,
+
+    /// Also copy each new match to the system clipboard, in the first '--format'
+    #[arg(long)]
+    clipboard: bool,
+
+    /// Minutes between matches that start a new session for '--sessionize'
+    #[arg(long, default_value_t = 30, requires = "sessionize")]
+    session_gap_mins: u64,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    Csv,
+    Json,
+    Ndjson,
+    Report,
+    Raw,
+    Xml,
+}
+
+/// What to do with matches missing a '--require' column
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum RequireMode {
+    Strict,
+    Warn,
+}
+
+/// What to replace negative counters with for '--clamp-negatives'
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Clamp {
+    Zero,
+    Empty,
+}
+
+/// What '--dedup-key' compares to decide a match is a duplicate
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum DedupKey {
+    Content,
+    Lobby,
+}
+
+/// Which file watcher '--watch-backend' uses
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum WatchBackend {
+    Auto,
+    Inotify,
+    Poll,
+    Fsevent,
+}
+
+/// How to decide which existing output file is the latest
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum LatestBy {
+    Mtime,
+    Filename,
+}
+
+impl Format {
+    fn extension(&self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::Json => "json",
+            Format::Ndjson => "ndjson",
+            Format::Report => "txt",
+            Format::Raw => "raw",
+            Format::Xml => "xml",
+        }
+    }
+
+    /// The format written to files with the given extension
+    fn from_extension(extension: &str) -> Option<Format> {
+        Format::value_variants()
+            .iter()
+            .copied()
+            .find(|f| extension.eq_ignore_ascii_case(f.extension()))
+    }
+
+    fn render(&self, args: &Args, match_data: &Match) -> String {
+        match self {
+            Format::Csv => render_csv(args, match_data),
+            Format::Json => render_json(args, match_data),
+            Format::Ndjson => render_ndjson(args, match_data),
+            Format::Report => render_report(args, match_data),
+            Format::Raw => render_raw(match_data),
+            Format::Xml => render_xml(args, match_data),
+        }
+    }
 }
 
 #[derive(Deserialize, Debug)]
@@ -49,14 +607,225 @@ struct Attributes {
 }
 
 #[derive(Deserialize, Debug, Clone)]
+#[serde(from = "RawItem")]
 struct Item {
+    name: String,
+    value: String,
+}
+
+/// An 'Attr' element as written by any game version, with its value either in a 'value'
+/// attribute or as text content
+#[derive(Deserialize)]
+struct RawItem {
     #[serde(rename = "@name")]
     name: String,
 
     #[serde(rename = "@value")]
-    value: String,
+    value: Option<String>,
+
+    #[serde(rename = "$text")]
+    text: Option<String>,
+}
+
+impl From<RawItem> for Item {
+    fn from(raw: RawItem) -> Item {
+        Item {
+            name: raw.name,
+            value: raw.value.or(raw.text).unwrap_or_default(),
+        }
+    }
 }
 
+/// Player data for a single match
+#[derive(Debug, Clone)]
+struct Match {
+    /// Number of players declared for each team
+    team_sizes: Vec<u32>,
+
+    /// Original number of each team, when '--compact-empty-teams' has left out teams before it
+    /// without '--renumber'. Empty when every team keeps its index as its number
+    team_numbers: Vec<u32>,
+
+    /// Names of the values in each row, starting with `HEADERS`
+    columns: Vec<String>,
+
+    rows: Vec<PlayerRow>,
+
+    /// Time the match was saved, written by '--include-timestamp-column'
+    timestamp: Option<String>,
+
+    /// Every attribute in the file, in order, kept only for '--format raw'
+    attributes: Vec<(String, String)>,
+}
+
+impl Match {
+    /// Number written for a team, counting from 1 unless '--zero-based'
+    fn team_output(&self, args: &Args, team: u32) -> u32 {
+        let offset = if args.zero_based { 0 } else { 1 };
+        self.team_numbers
+            .get(team as usize)
+            .copied()
+            .unwrap_or(team)
+            + offset
+    }
+
+    fn column_index(&self, column: &str) -> Option<usize> {
+        self.columns.iter().position(|c| c == column)
+    }
+
+    /// Gets the value of the named column for a row
+    fn value<'a>(&self, row: &'a PlayerRow, column: &str) -> Option<&'a str> {
+        row.values
+            .get(self.column_index(column)?)
+            .map(String::as_str)
+    }
+
+    /// Appends a column to every row, with values computed from that row
+    fn push_column<F: Fn(&Match, &PlayerRow) -> String>(&mut self, name: &str, value: F) {
+        let values: Vec<String> = self.rows.iter().map(|row| value(self, row)).collect();
+        for (row, value) in self.rows.iter_mut().zip(values) {
+            row.values.push(value);
+        }
+        self.columns.push(name.to_string());
+    }
+
+    /// Rebuilds a match from a saved CSV file, or None if a row's team or player number is
+    /// invalid
+    fn from_saved(args: &Args, saved: &SavedMatch) -> Option<Match> {
+        let offset = if args.zero_based { 0 } else { 1 };
+        let columns: Vec<String> = saved
+            .columns
+            .iter()
+            .filter(|c| !matches!(c.as_str(), "Team" | "Player" | "match_timestamp"))
+            .cloned()
+            .collect();
+
+        let mut team_sizes: Vec<u32> = Vec::new();
+        let mut rows = Vec::new();
+        for row in &saved.rows {
+            let number = |column: &str| -> Option<u32> {
+                saved
+                    .value(row, column)?
+                    .parse::<u32>()
+                    .ok()?
+                    .checked_sub(offset)
+            };
+            let (team, player) = (number("Team")?, number("Player")?);
+            if team_sizes.len() <= team as usize {
+                team_sizes.resize(team as usize + 1, 0);
+            }
+            team_sizes[team as usize] += 1;
+
+            rows.push(PlayerRow {
+                team,
+                player,
+                values: columns
+                    .iter()
+                    .map(|c| saved.value(row, c).unwrap_or_default().to_string())
+                    .collect(),
+            });
+        }
+
+        Some(Match {
+            team_sizes,
+            team_numbers: Vec::new(),
+            columns,
+            rows,
+            timestamp: saved
+                .rows
+                .first()
+                .and_then(|row| saved.value(row, "match_timestamp"))
+                .map(str::to_string),
+            attributes: Vec::new(),
+        })
+    }
+
+    /// Copies the match with only the rows for which `keep` returns true
+    fn filter_rows<F: Fn(&PlayerRow) -> bool>(&self, keep: F) -> Match {
+        Match {
+            rows: self.rows.iter().filter(|row| keep(row)).cloned().collect(),
+            ..self.clone()
+        }
+    }
+
+    /// Splits the match into the parts saved as separate files, by team for
+    /// '--per-team-files' or into '--rows-per-file' rows each, along with the suffix added to
+    /// each part's file stem
+    fn split(&self, args: &Args) -> Vec<(String, Match)> {
+        if args.per_team_files && !self.rows.is_empty() {
+            let teams: BTreeSet<u32> = self.rows.iter().map(|row| row.team).collect();
+            return teams
+                .into_iter()
+                .map(|team| {
+                    (
+                        format!("_team{}", self.team_output(args, team)),
+                        self.filter_rows(|row| row.team == team),
+                    )
+                })
+                .collect();
+        }
+
+        match args.rows_per_file {
+            Some(n) if self.rows.len() as u64 > n => self
+                .rows
+                .chunks(n as usize)
+                .enumerate()
+                .map(|(i, rows)| {
+                    let part = Match {
+                        rows: rows.to_vec(),
+                        ..self.clone()
+                    };
+                    (format!("_{}", i + 1), part)
+                })
+                .collect(),
+            _ => vec![(String::new(), self.clone())],
+        }
+    }
+}
+
+/// A single player's values for a match, in the same order as `Match::columns`
+#[derive(Debug, Clone)]
+struct PlayerRow {
+    /// Zero-based team index
+    team: u32,
+
+    /// Zero-based player index within the team
+    player: u32,
+
+    values: Vec<String>,
+}
+
+/// Default path of 'attributes.xml', for the 'default' profile
+const DEFAULT_INPUT: &str = r"C:\Program Files (x86)\Steam\steamapps\common\Hunt Showdown\user\profiles\default\attributes.xml";
+
+/// How long to wait before reading 'attributes.xml' again when it has no attributes at all
+const EMPTY_REREAD_DELAY: Duration = Duration::from_millis(500);
+
+/// Byte order mark written at the start of CSV files by '--bom'
+const BOM: char = '\u{feff}';
+
+/// Format of the timestamp used to name output files
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d_%H-%M-%S";
+
+/// File stem of the link to the newest output file made by '--latest-symlink'
+const LATEST_LINK_STEM: &str = "latest";
+
+/// Extensions of the files written by '--split-teams', before '.csv'
+const SPLIT_TEAM_EXTENSIONS: &[&str] = &["mine", "enemies"];
+
+/// Inner extension of the files written by '--events'
+const EVENTS_EXTENSION: &str = "events";
+
+/// Bosses in the order of their index in 'MissionBagBoss_<index>' attributes
+const BOSSES: &[&str] = &[
+    "butcher",
+    "spider",
+    "assassin",
+    "scrapbeak",
+    "rotjaw",
+    "hellborn",
+];
+
 const HEADERS: &[&str] = &[
     "blood_line_name",
     "mmr",
@@ -77,144 +846,2638 @@ const HEADERS: &[&str] = &[
     "profileid",
 ];
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let args = Args::parse();
+/// Columns counting downs and kills, which '--clamp-negatives' applies to
+const COUNTERS: &[&str] = &[
+    "downedbyme",
+    "killedbyme",
+    "downedme",
+    "killedme",
+    "downedbyteammate",
+    "killedbyteammate",
+    "downedteammate",
+    "killedteammate",
+];
+
+/// Human readable description of an output column
+fn column_description(column: &str) -> &'static str {
+    match column {
+        "Team" => "Team number",
+        "Player" => "Player number within the team",
+        "blood_line_name" => "Hunter name",
+        "mmr" => "Matchmaking rating",
+        "skillbased" => "Whether matchmaking was skill based",
+        "downedbyme" => "Times you downed this hunter",
+        "killedbyme" => "Times you killed this hunter",
+        "downedme" => "Times this hunter downed you",
+        "killedme" => "Times this hunter killed you",
+        "proximitytome" => "Whether this hunter came near you",
+        "downedbyteammate" => "Times your teammates downed this hunter",
+        "killedbyteammate" => "Times your teammates killed this hunter",
+        "downedteammate" => "Times this hunter downed your teammates",
+        "killedteammate" => "Times this hunter killed your teammates",
+        "proximitytoteammate" => "Whether this hunter came near your teammates",
+        "bountypickedup" => "Whether this hunter picked up a bounty",
+        "bountyextracted" => "Whether this hunter extracted with a bounty",
+        "teamextraction" => "Whether this hunter's team extracted",
+        "profileid" => "Profile ID of this hunter",
+        "region" => "Server region of the match",
+        "boss_butcher" => "Whether the Butcher was in the match",
+        "boss_spider" => "Whether the Spider was in the match",
+        "boss_assassin" => "Whether the Assassin was in the match",
+        "boss_scrapbeak" => "Whether Scrapbeak was in the match",
+        "boss_rotjaw" => "Whether Rotjaw was in the match",
+        "boss_hellborn" => "Whether the Hellborn was in the match",
+        "outcome" => "How the match ended for you",
+        "match_timestamp" => "Time the match was saved",
+        "team_mmr" => "Average MMR of this hunter's team",
+        "had_interaction" => "Whether you downed or killed this hunter or it downed or killed you",
+        "is_random" => "Whether this hunter's team was matched with random teammates",
+        "is_partner" => "Whether this hunter was your teammate",
+        "mmr_bracket" => "Named bracket of this hunter's MMR",
+        "seat" => "Player number within the lobby",
+        "team_won" => "Whether this hunter's team extracted",
+        "mmr_normalized" => "Matchmaking rating scaled to between 0 and 1",
+        "mmr_rank" => "Position of this hunter's MMR in the lobby where 1 is the highest",
+        _ => "",
+    }
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let mut matches = Args::command().get_matches();
+    if let Some(path) = matches.get_one::<String>("config") {
+        matches = settings::with_settings_file(Args::command(), &matches, Path::new(path))?;
+    }
+    let mut args = Args::from_arg_matches(&matches)?;
+    CONSOLE_TO_STDERR.store(args.json_result, Ordering::Relaxed);
+
+    if let Some(path) = &args.export_config {
+        return settings::export_settings(&Args::command(), &matches, Path::new(path));
+    }
+
+    if args.setup {
+        return config::run_setup();
+    }
+
+    if let [a, b] = args.compare.as_slice() {
+        let read = |path: &String| {
+            SavedMatch::read(Path::new(path))
+                .map_err(|reason| format!("Could not read '{path}': {reason}"))
+        };
+        let (old, new) = (read(a)?, read(b)?);
+        print!(
+            "{}",
+            diff::compare_matches(&old, &new, args.color.enabled())
+        );
+        return Ok(());
+    }
+
+    if args.emit_schema {
+        println!("{}", json_schema(&args));
+        return Ok(());
+    }
+
+    if args.print_stats {
+        return Ok(stats::print_stats(
+            &args,
+            Path::new(args.stats_db.as_ref().unwrap()),
+        )?);
+    }
+
+    // An explicit '--input' takes precedence over '--profile', which takes precedence over the
+    // environment variable and the config file
+    match (&args.profile, matches.value_source("input")) {
+        (Some(profile), Some(source)) if source != ValueSource::CommandLine => {
+            args.input =
+                DEFAULT_INPUT.replace(r"\profiles\default\", &format!(r"\profiles\{profile}\"));
+        }
+        (_, Some(ValueSource::DefaultValue)) => {
+            if let Some(input) = Config::load()?.input {
+                args.input = input;
+            }
+        }
+        _ => {}
+    }
+
+    if let Some(output_file) = &args.output_file {
+        let extension = Path::new(output_file)
+            .extension()
+            .unwrap_or_default()
+            .to_string_lossy();
+        if Format::from_extension(&extension).is_none() {
+            let extensions: Vec<&str> = Format::value_variants()
+                .iter()
+                .map(Format::extension)
+                .collect();
+            return Err(format!(
+                "Can't tell the format of '--output-file' '{output_file}' from its extension, \
+                expected one of: {}",
+                extensions.join(", ")
+            )
+            .into());
+        }
+    }
+
+    // Lobbies are compared using the latest saved CSV file
+    if args.dedup_key == DedupKey::Lobby && !args.format.contains(&Format::Csv) {
+        return Err("'--dedup-key lobby' requires 'csv' in '--format'".into());
+    }
+
+    if args.input == "-" && !args.single {
+        return Err("Reading 'attributes.xml' from stdin requires '--single'".into());
+    }
+
+    if Encoding::for_label(args.encoding.as_bytes()).is_none() {
+        return Err(format!("Unknown encoding label '{}'", args.encoding).into());
+    }
 
-    let user_dir = UserDirs::new();
+    // Service accounts may have no home or documents directory to default to
     let output_dir_path = match &args.output_dir {
         Some(p) => PathBuf::from(p),
-        None => match &user_dir {
-            Some(ud) => ud.document_dir().unwrap().join("Hunt").join("MatchData"),
+        None => match UserDirs::new().as_ref().and_then(UserDirs::document_dir) {
+            Some(documents) => documents.join("Hunt").join("MatchData"),
+            // Matches are saved to '--output-file' rather than the output directory
+            None if args.output_file.is_some() => Path::new(args.output_file.as_ref().unwrap())
+                .parent()
+                .unwrap_or(Path::new(""))
+                .to_path_buf(),
+            // Nothing is saved with '--no-local'
+            None if args.no_local => PathBuf::new(),
             None => {
-                panic!("Could not obtain handle to Home directory")
+                return Err(
+                    "Could not find a documents directory to save matches in, pass \
+                    '--output-dir' to choose where to save them"
+                        .into(),
+                )
             }
         },
     };
 
-    if !args.single {
-        println!("Watching for changes to 'attributes.xml'...");
-        let (tx, rx) = std::sync::mpsc::channel();
-        let mut debouncer = new_debouncer(Duration::from_secs(2), None, tx).unwrap();
-        debouncer
-            .watcher()
-            .watch(args.input.as_ref(), RecursiveMode::Recursive)?;
+    if args.no_local && args.post_url.is_none() && args.sse_port.is_none() {
+        return Err("'--no-local' needs '--post-url' or '--sse-port' to send matches to".into());
+    }
 
-        for res in rx {
-            match res {
-                Ok(_) => extract_player_data(&args, output_dir_path.as_path())?,
-                Err(e) => println!("watch error: {e:?}"),
-            }
-        }
-    } else {
-        extract_player_data(&args, output_dir_path.as_path())?;
+    // Creating the directory would otherwise fail with a confusing error part way through
+    if output_dir_path.exists() && !output_dir_path.is_dir() {
+        return Err(format!(
+            "The output directory '{}' exists but is a file, pass '--output-dir' with a \
+            directory to save matches in",
+            output_dir_path.to_string_lossy()
+        )
+        .into());
     }
 
-    Ok(())
-}
+    if args.health_check {
+        return health::health_check(&args, &output_dir_path);
+    }
 
-fn extract_player_data<P: AsRef<Path>>(
-    args: &Args,
-    output_dir_path: P,
-) -> Result<(), Box<dyn Error>> {
-    let contents = fs::read_to_string(&args.input).expect("Could not open file.");
-    let attributes: Attributes = from_str(contents.as_str()).unwrap();
+    if args.backfill_from_steam_cloud {
+        return backfill_from_steam_cloud(&args, &output_dir_path);
+    }
 
-    let output_file_path = PathBuf::from(output_dir_path.as_ref()).join(&args.temp_file);
+    if args.list_matches {
+        return Ok(list::list_matches(&args, &output_dir_path)?);
+    }
 
-    fs::create_dir_all(&output_dir_path).expect("Could not create output directory.");
+    if let Some(merge_path) = &args.merge {
+        return Ok(merge::merge_csv_files(
+            &args,
+            &output_dir_path,
+            Path::new(merge_path),
+        )?);
+    }
 
-    // Grab a reference to the latest existing CSV file, if it exists, for comparison later
-    let mut existing_files: Vec<fs::DirEntry> = fs::read_dir(&output_dir_path)
-        .expect("Could not access output directory")
-        .filter(|r| match r {
-            Ok(de) => {
-                de.metadata().unwrap().is_file()
-                    && de.path().extension().unwrap() == "csv"
-                    && de.file_name() != args.temp_file.as_str()
-            }
-            _ => false,
-        })
-        .flatten()
-        .collect();
-    existing_files.sort_by_cached_key(|f| f.metadata().unwrap().modified().unwrap());
-    let latest_csv = existing_files.last();
+    if let Some(sessions_path) = &args.sessionize {
+        return Ok(sessions::write_sessions(
+            &args,
+            &output_dir_path,
+            Path::new(sessions_path),
+        )?);
+    }
 
-    // Build map of names to values from attributes file
-    let mut attr_map = HashMap::new();
-    for item in attributes.items.iter() {
-        attr_map.insert(&item.name, &item.value);
+    if args.recompute_derived {
+        return Ok(recompute::recompute_saved_files(&args, &output_dir_path)?);
     }
 
-    // Check if attributes file has team data, and get the number of teams
-    if let Some(num_teams) = attr_map.get(&"MissionBagNumTeams".to_string()) {
-        let temp_file = fs::File::options()
-            .read(true)
-            .write(true)
-            .truncate(true)
-            .create(true)
-            .open(&output_file_path)?;
-        let mut temp_file = BufWriter::new(temp_file);
-
-        // Write CSV header row
-        temp_file.write_all(format!("Team,Player,{}", HEADERS.join(",")).as_bytes())?;
-
-        // Get number of players in each team
-        let mut num_players = Vec::new();
-        for team in 0..num_teams.parse::<u32>()? {
-            num_players.push(
-                attr_map
-                    .get(&format!("MissionBagTeam_{team}_numplayers"))
-                    .unwrap()
-                    .parse::<u32>()?,
-            );
-        }
+    if args.prune_temp_on_start {
+        prune_temp_files(&args, &output_dir_path);
+    }
 
-        // Iterate over players in each team, collecting attributes that exist in HEADERS array
-        for (team, &team_size) in num_players.iter().enumerate() {
-            for player in 0..team_size {
-                let team_output = team + if args.zero_based { 0 } else { 1 };
-                let player_output = player + if args.zero_based { 0 } else { 1 };
-                temp_file.write_all(format!("\n{team_output},{player_output}").as_bytes())?;
+    if let Some(port) = args.sse_port {
+        sse::start(port)?;
+    }
 
-                for header in HEADERS {
-                    let value = *attr_map
-                        .get(&format!("MissionBagPlayer_{team}_{player}_{header}"))
-                        .unwrap();
-
-                    // Escape commas for CSV
-                    if value.contains(',') {
-                        temp_file.write_all(format!(",\"{value}\"").as_bytes())?;
-                    } else {
-                        temp_file.write_all(format!(",{value}").as_bytes())?;
-                    }
-                }
-            }
-        }
+    if let (Some(url), Some(spool_dir)) = (&args.post_url, spool_dir(&args, &output_dir_path)) {
+        sink::retry_spooled(url, &spool_dir);
     }
 
-    // If the existing latest CSV file matches the newly created one, or if it does not exist,
-    // then rename temp file with a timestamp
-    let new_contents = fs::read_to_string(&output_file_path)
-        .expect("Could not read newly created temporary CSV file.");
-    if match latest_csv {
-        Some(de) => {
-            let existing_contents =
-                fs::read_to_string(de.path()).expect("Could not read existing latest CSV file.");
+    if args.replay {
+        return replay::replay(&args, &output_dir_path);
+    }
 
-            new_contents != existing_contents
-        }
-        None => true,
-    } {
-        let timestamp = Local::now().format("%Y-%m-%d_%H-%M-%S");
-        let final_path = output_dir_path.as_ref().join(format!("{timestamp}.csv"));
-        fs::rename(output_file_path, &final_path)
-            .expect("Could not rename temporary CSV file with timestamp.");
-        println!("{new_contents}");
-        println!(
-            "New player summary saved: '{}'",
-            final_path.to_string_lossy()
+    if is_zip_archive(&args.input) {
+        return Ok(extract_archive(&args, &output_dir_path)?);
+    }
+
+    if !args.single {
+        watch(&args, &output_dir_path)?;
+    } else {
+        let extraction = extract_player_data(&args, output_dir_path.as_path())?;
+        if args.json_result {
+            println!("{}", extraction.json_result());
+        }
+    }
+
+    Ok(())
+}
+
+/// Starts the '--watch-backend' file watcher on the input, returning it boxed as its type
+/// depends on the backend
+fn watch_input(
+    args: &Args,
+    tx: Sender<DebounceEventResult>,
+) -> Result<Box<dyn Any>, Box<dyn Error>> {
+    // Only the poll watcher reads the config
+    let config = notify::Config::default().with_poll_interval(Duration::from_secs(1));
+    match args.watch_backend {
+        WatchBackend::Auto => watch_with::<RecommendedWatcher>(args, tx, config),
+        WatchBackend::Poll => watch_with::<PollWatcher>(args, tx, config),
+        #[cfg(target_os = "linux")]
+        WatchBackend::Inotify => watch_with::<INotifyWatcher>(args, tx, config),
+        #[cfg(target_os = "macos")]
+        WatchBackend::Fsevent => watch_with::<FsEventWatcher>(args, tx, config),
+        #[allow(unreachable_patterns)]
+        backend => Err(format!(
+            "'--watch-backend {}' is not available on this platform",
+            backend.to_possible_value().unwrap().get_name()
+        )
+        .into()),
+    }
+}
+
+/// Starts a debounced watcher of type `W` on the input
+fn watch_with<W: Watcher + 'static>(
+    args: &Args,
+    tx: Sender<DebounceEventResult>,
+    config: notify::Config,
+) -> Result<Box<dyn Any>, Box<dyn Error>> {
+    let mut debouncer =
+        new_debouncer_opt::<_, W>(Duration::from_millis(args.debounce_ms), None, tx, config)?;
+    debouncer
+        .watcher()
+        .watch(args.input.as_ref(), RecursiveMode::Recursive)?;
+    Ok(Box::new(debouncer))
+}
+
+/// Extracts player data whenever 'attributes.xml' changes, until '--watch-timeout' passes
+/// without changes or '--limit' new matches have been saved
+fn watch(args: &Args, output_dir_path: &Path) -> Result<(), Box<dyn Error>> {
+    let mut last_match = None;
+    let mut new_matches = 0;
+    let limit_reached = |new_matches| {
+        let reached = args.limit.is_some_and(|limit| new_matches >= limit);
+        if reached {
+            console!("Saved '--limit' of {new_matches} new matches, exiting");
+        }
+        reached
+    };
+
+    // A failed extraction, e.g. of a partly written file, is logged and the next change waited
+    // for rather than ending the watch
+    let extract = || match extract_player_data(args, output_dir_path) {
+        Ok(extraction) => extraction.is_new(),
+        Err(e) => {
+            console!("Could not extract player data: {e}");
+            false
+        }
+    };
+
+    if args.watch_existing && extract() {
+        last_match = Some(Local::now());
+        new_matches += 1;
+        if limit_reached(new_matches) {
+            return Ok(());
+        }
+    }
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    // Kept alive until the loop below ends
+    let _debouncer = match args.poll {
+        Some(interval) => {
+            console!("Polling 'attributes.xml' for changes every {interval} seconds...");
+            let input = PathBuf::from(&args.input);
+            std::thread::spawn(move || poll_input(&input, Duration::from_secs(interval), tx));
+            None
+        }
+        None => {
+            console!("Watching for changes to 'attributes.xml'...");
+            Some(watch_input(args, tx)?)
+        }
+    };
+
+    let timeout = Duration::from_secs(args.watch_timeout);
+    let heartbeat = Duration::from_secs(args.heartbeat);
+    let mut last_event = Instant::now();
+    let mut last_heartbeat = Instant::now();
+
+    loop {
+        // Wake up for whichever of the timeout or the next heartbeat comes first
+        let wait = [
+            (args.watch_timeout > 0).then(|| timeout.saturating_sub(last_event.elapsed())),
+            (args.heartbeat > 0).then(|| heartbeat.saturating_sub(last_heartbeat.elapsed())),
+        ]
+        .into_iter()
+        .flatten()
+        .min();
+
+        let res = match wait {
+            None => match rx.recv() {
+                Ok(res) => res,
+                Err(_) => break,
+            },
+            Some(wait) => match rx.recv_timeout(wait) {
+                Ok(res) => res,
+                Err(RecvTimeoutError::Timeout) => {
+                    if args.watch_timeout > 0 && last_event.elapsed() >= timeout {
+                        console!("No changes for {} seconds, exiting", args.watch_timeout);
+                        break;
+                    }
+                    if args.heartbeat > 0 && last_heartbeat.elapsed() >= heartbeat {
+                        match last_match {
+                            Some(time) => console!(
+                                "Still watching, last match at {}",
+                                time.format("%Y-%m-%d %H:%M:%S")
+                            ),
+                            None => console!("Still watching, no matches yet"),
+                        }
+                        last_heartbeat = Instant::now();
+                    }
+                    continue;
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+            },
+        };
+        last_event = Instant::now();
+
+        match res {
+            Ok(_) => {
+                if extract() {
+                    last_match = Some(Local::now());
+                    if args.timings {
+                        print_latency(Path::new(&args.input));
+                    }
+                    new_matches += 1;
+                    if limit_reached(new_matches) {
+                        break;
+                    }
+                }
+            }
+            Err(e) => console!("watch error: {e:?}"),
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints how long ago the input was modified, which includes the time spent waiting for the
+/// debouncer or the next poll
+fn print_latency(input: &Path) {
+    let elapsed = fs::metadata(input)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok());
+    if let Some(elapsed) = elapsed {
+        console!(
+            "Latency from file change to output: {:.3} ms",
+            elapsed.as_secs_f64() * 1000.0
+        );
+    }
+}
+
+/// Modification time and size of the input, used to tell whether it has changed
+fn input_stamp(input: &Path) -> Option<(SystemTime, u64)> {
+    fs::metadata(input)
+        .and_then(|m| Ok((m.modified()?, m.len())))
+        .ok()
+}
+
+/// Sends an empty event whenever the modification time or size of `input` changes, until
+/// the receiver is dropped
+fn poll_input(input: &Path, interval: Duration, tx: Sender<DebounceEventResult>) {
+    let mut last_stamp = input_stamp(input);
+    loop {
+        std::thread::sleep(interval);
+        let new_stamp = input_stamp(input);
+        if new_stamp != last_stamp {
+            last_stamp = new_stamp;
+            if tx.send(Ok(Vec::new())).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+fn extract_player_data<P: AsRef<Path>>(
+    args: &Args,
+    output_dir_path: P,
+) -> Result<Extraction, ExtractError> {
+    // Modification time and size of the input when it was last processed
+    static LAST_PROCESSED: Mutex<Option<(SystemTime, u64)>> = Mutex::new(None);
+
+    let start = Instant::now();
+    if args.input == "-" {
+        let mut bytes = Vec::new();
+        std::io::stdin().read_to_end(&mut bytes)?;
+        timings::record("File read", start);
+        let extraction = process_attributes(args, &bytes, output_dir_path);
+        dump_if_no_match(args, &bytes, &extraction);
+        timings::finish(args.timings);
+        return extraction;
+    }
+
+    // Skip events where only metadata such as the access time changed
+    let stamp = input_stamp(Path::new(&args.input));
+    if stamp.is_some() && *LAST_PROCESSED.lock().unwrap() == stamp {
+        return Ok(Extraction::NoMatch);
+    }
+
+    let mut bytes = fs::read(&args.input)?;
+    timings::record("File read", start);
+    let mut extraction = process_attributes(args, &bytes, output_dir_path.as_ref());
+    let mut stamp = stamp;
+
+    // A file with no attributes at all was most likely read while the game was writing it,
+    // and the team count can be written shortly after the rest of the match
+    let no_match = !args.single && matches!(extraction, Ok(Extraction::NoMatch));
+    let empty = no_match && attribute_count(args, &bytes) == Some(0);
+    let reread_delay = if empty {
+        console!(
+            "'attributes.xml' has no attributes, reading it again in {} ms in case it was being \
+            written",
+            EMPTY_REREAD_DELAY.as_millis()
+        );
+        Some(EMPTY_REREAD_DELAY)
+    } else if no_match && args.no_match_grace_ms > 0 {
+        Some(Duration::from_millis(args.no_match_grace_ms))
+    } else {
+        None
+    };
+    if let Some(delay) = reread_delay {
+        std::thread::sleep(delay);
+        stamp = input_stamp(Path::new(&args.input));
+        bytes = fs::read(&args.input)?;
+        extraction = process_attributes(args, &bytes, output_dir_path.as_ref());
+        if empty
+            && matches!(extraction, Ok(Extraction::NoMatch))
+            && attribute_count(args, &bytes) == Some(0)
+        {
+            console!("'attributes.xml' is still empty, so there is no match");
+        }
+    }
+    // Only the final read is dumped, so one event never saves the same file twice
+    dump_if_no_match(args, &bytes, &extraction);
+    timings::finish(args.timings);
+    *LAST_PROCESSED.lock().unwrap() = stamp;
+
+    extraction
+}
+
+/// What became of the contents of an 'attributes.xml' file
+enum Extraction {
+    /// There was no team data, or the file has not changed since it was last read
+    NoMatch,
+
+    /// The match was left out by '--min-players' or '--require-mode strict'
+    Skipped { teams: usize, players: usize },
+
+    /// The match was the same as the latest output file
+    Duplicate { teams: usize, players: usize },
+
+    /// The match was new, and saved to this first output file
+    Saved {
+        path: PathBuf,
+        teams: usize,
+        players: usize,
+    },
+
+    /// The match was new, and only sent to the sinks because of '--no-local'
+    Relayed { teams: usize, players: usize },
+}
+
+impl Extraction {
+    fn is_saved(&self) -> bool {
+        matches!(self, Extraction::Saved { .. })
+    }
+
+    fn is_new(&self) -> bool {
+        matches!(self, Extraction::Saved { .. } | Extraction::Relayed { .. })
+    }
+
+    /// Describes the extraction as a JSON object for '--json-result'
+    fn json_result(&self) -> String {
+        let (path, counts) = match self {
+            Extraction::NoMatch => (None, None),
+            Extraction::Skipped { teams, players }
+            | Extraction::Duplicate { teams, players }
+            | Extraction::Relayed { teams, players } => (None, Some((teams, players))),
+            Extraction::Saved {
+                path,
+                teams,
+                players,
+            } => (Some(path.to_string_lossy()), Some((teams, players))),
+        };
+        serde_json::to_string(&JsonResult {
+            written: self.is_saved(),
+            path,
+            teams: counts.map(|(&teams, _)| teams),
+            players: counts.map(|(_, &players)| players),
+            duplicate: matches!(self, Extraction::Duplicate { .. }),
+        })
+        .unwrap()
+    }
+}
+
+/// Output of '--json-result', with fields in the order they are written
+#[derive(Serialize)]
+struct JsonResult<'a> {
+    written: bool,
+    path: Option<Cow<'a, str>>,
+    teams: Option<usize>,
+    players: Option<usize>,
+    duplicate: bool,
+}
+
+/// Extracts player data from the raw contents of an 'attributes.xml' file into the output
+/// directory
+fn process_attributes<P: AsRef<Path>>(
+    args: &Args,
+    bytes: &[u8],
+    output_dir_path: P,
+) -> Result<Extraction, ExtractError> {
+    let encoding = Encoding::for_label(args.encoding.as_bytes()).unwrap();
+    let (contents, _, _) = encoding.decode(bytes);
+
+    // Nothing to write if the attributes file has no team data
+    let mut match_data = match parse_match(args, &contents)? {
+        Some(match_data) => match_data,
+        None => return Ok(Extraction::NoMatch),
+    };
+    let teams = match_data.team_sizes.len();
+    let players = match_data.rows.len();
+    if match_data.rows.len() < args.min_players {
+        console!(
+            "Skipping match with {} players, fewer than '--min-players' {}",
+            match_data.rows.len(),
+            args.min_players
+        );
+        return Ok(Extraction::Skipped { teams, players });
+    }
+
+    if let (Some(output_file), Some(format)) = (&args.output_file, output_file_format(args)) {
+        let path = PathBuf::from(output_file);
+        fs::write(&path, format.render(args, &match_data))?;
+        console!("Player summary saved: '{output_file}'");
+        return Ok(Extraction::Saved {
+            path,
+            teams,
+            players,
+        });
+    }
+
+    let missing = missing_required(args, &match_data);
+    if !missing.is_empty() && args.require_mode == RequireMode::Strict {
+        console!(
+            "Skipping match missing required values: {}",
+            missing.join(", ")
+        );
+        return Ok(Extraction::Skipped { teams, players });
+    }
+
+    let timestamp = free_timestamp(output_dir_path.as_ref());
+    match_data.timestamp = Some(timestamp.clone());
+    if args.no_local {
+        return Ok(relay_match(args, match_data));
+    }
+    let primary_format = args.format[0];
+    let render_parts = |match_data: &Match| -> Vec<String> {
+        match_data
+            .split(args)
+            .iter()
+            .map(|(_, part)| primary_format.render(args, part))
+            .collect()
+    };
+    let (part_suffixes, new_parts) = match &args.exec {
+        Some(command) => (
+            vec![String::new()],
+            vec![run_exec(args, command, &contents)?],
+        ),
+        None => (
+            match_data
+                .split(args)
+                .into_iter()
+                .map(|(suffix, _)| suffix)
+                .collect(),
+            render_parts(&match_data),
+        ),
+    };
+
+    let output_file_path = PathBuf::from(output_dir_path.as_ref()).join(temp_file_name(args));
+
+    fs::create_dir_all(&output_dir_path)?;
+
+    // Grab a reference to the latest existing output file, if it exists, for comparison later
+    let lookup_start = Instant::now();
+    let latest_output = latest_output_file(args, &output_dir_path, primary_format.extension())?;
+    let latest_csv = match primary_format {
+        Format::Csv => latest_output.clone(),
+        _ => latest_output_file(args, &output_dir_path, Format::Csv.extension())?,
+    };
+    timings::record("Latest file lookup", lookup_start);
+
+    let write_start = Instant::now();
+    fs::write(&output_file_path, &new_parts[0])?;
+    timings::record("Temporary file write", write_start);
+
+    let compare_start = Instant::now();
+    // The timestamp column differs for every match, so compare using the latest file's own
+    // timestamp instead
+    let comparable_parts = match &latest_output {
+        Some(path) if args.include_timestamp_column && args.exec.is_none() => {
+            let mut previous = match_data.clone();
+            previous.timestamp =
+                file_timestamp(path).map(|t| t.format(TIMESTAMP_FORMAT).to_string());
+            render_parts(&previous)
+        }
+        _ => new_parts.clone(),
+    };
+
+    // If the existing latest output file matches the newly created one, or if it does not exist,
+    // then rename temp file with a timestamp and write any other formats alongside it
+    let comparison = match args.dedup_key {
+        _ if args.no_dedup => None,
+        DedupKey::Content => latest_output.as_ref().map(|path| {
+            let differs = read_latest_parts(path).map(|existing| comparable_parts != existing);
+            (path, differs)
+        }),
+        DedupKey::Lobby => latest_csv.as_ref().map(|path| {
+            let new = SavedMatch::parse(&render_csv(args, &match_data));
+            let differs = read_saved_parts(path)
+                .map(|existing| lobby_key(args, &new) != lobby_key(args, &existing));
+            (path, differs)
+        }),
+    };
+    let is_new = args.no_dedup
+        || match comparison {
+            Some((_, Ok(differs))) => differs,
+            // The latest file may have been removed since listing the directory
+            Some((path, Err(e))) => {
+                console!(
+                    "Warning: could not read latest output file '{}' for comparison: {e}",
+                    path.to_string_lossy()
+                );
+                true
+            }
+            None => true,
+        };
+    timings::record("Dedup comparison", compare_start);
+    if is_new {
+        if args.diff_latest {
+            match latest_csv {
+                Some(path) => {
+                    let old = read_saved_parts(&path)?;
+                    let new = SavedMatch::parse(&render_csv(args, &match_data));
+                    console!(
+                        "Changes from previous match:\n{}",
+                        diff_matches(&old, &new, args.color.enabled()).trim_end_matches('\n')
+                    );
+                }
+                None => console!("No previous match to compare against"),
+            }
+        }
+
+        let write_start = Instant::now();
+        let final_paths = output_part_paths(
+            output_dir_path.as_ref(),
+            &timestamp,
+            primary_format.extension(),
+            &part_suffixes,
         );
+        // Only another instance saving at the same moment can have taken the timestamp
+        if let Some(path) = final_paths.iter().find(|p| p.exists()) {
+            let _ = fs::remove_file(&output_file_path);
+            return Err(ExtractError::OutputExists { path: path.clone() });
+        }
+        move_file(&output_file_path, &final_paths[0])?;
+        for (path, part) in final_paths.iter().zip(&new_parts).skip(1) {
+            fs::write(path, part)?;
+        }
+        remember_latest_parts(&final_paths, new_parts.clone());
+        let text = new_parts
+            .iter()
+            .map(|part| part.trim_start_matches(BOM).trim_end_matches('\n'))
+            .collect::<Vec<&str>>()
+            .join("\n");
+        if args.brief {
+            console!("{}", brief_summary(args, &match_data));
+        } else {
+            console!("{text}");
+        }
+        if args.mmr_histogram {
+            console!("{}", derived::mmr_histogram(args, &match_data));
+        }
+        if args.clipboard {
+            copy_to_clipboard(&text);
+        }
+        for path in &final_paths {
+            console!("New player summary saved: '{}'", path.to_string_lossy());
+        }
+        // Only the first part of each format is linked to by '--latest-symlink'
+        let mut saved_paths = vec![final_paths[0].clone()];
+
+        for format in args.format.iter().skip(1) {
+            let (suffixes, parts): (Vec<String>, Vec<Match>) =
+                match_data.split(args).into_iter().unzip();
+            let paths = output_part_paths(
+                output_dir_path.as_ref(),
+                &timestamp,
+                format.extension(),
+                &suffixes,
+            );
+            for (path, part) in paths.iter().zip(&parts) {
+                fs::write(path, format.render(args, part))?;
+                console!("New player summary saved: '{}'", path.to_string_lossy());
+            }
+            saved_paths.push(paths[0].clone());
+        }
+
+        if args.split_teams {
+            let my_team = match_data
+                .rows
+                .iter()
+                .find(|row| match_data.value(row, "profileid") == args.my_profile_id.as_deref())
+                .map(|row| row.team);
+            if my_team.is_none() {
+                console!("'--my-profile-id' is not in this match, so every team is an enemy");
+            }
+            for (extension, mine) in SPLIT_TEAM_EXTENSIONS.iter().zip([true, false]) {
+                let path = output_dir_path
+                    .as_ref()
+                    .join(format!("{timestamp}.{extension}.csv"));
+                let team = match_data.filter_rows(|row| (Some(row.team) == my_team) == mine);
+                fs::write(&path, render_csv(args, &team))?;
+                console!("New player summary saved: '{}'", path.to_string_lossy());
+            }
+        }
+
+        if args.events {
+            let path = output_dir_path
+                .as_ref()
+                .join(format!("{timestamp}.{EVENTS_EXTENSION}.csv"));
+            fs::write(&path, events::render_events(&contents)?)?;
+            console!("Match events saved: '{}'", path.to_string_lossy());
+        }
+        timings::record("Output file writes", write_start);
+
+        if let Some(url) = &args.post_url {
+            sink::post_match(
+                url,
+                spool_dir(args, output_dir_path.as_ref()).as_deref(),
+                &timestamp,
+                &render_json(args, &match_data),
+            );
+        }
+
+        if args.latest_symlink {
+            for path in &saved_paths {
+                if let Err(e) = update_latest_link(path) {
+                    console!(
+                        "Could not update latest link for '{}': {e}",
+                        path.to_string_lossy()
+                    );
+                }
+            }
+        }
+
+        if args.sse_port.is_some() {
+            sse::broadcast(&render_json(args, &match_data));
+        }
+
+        if let Some(path) = &args.kda_ledger {
+            if let Err(e) = append_kda(args, Path::new(path), &timestamp, &match_data) {
+                console!("Could not update KDA ledger '{path}': {e}");
+            }
+        }
+
+        if let Some(path) = &args.stats_db {
+            if let Err(e) = stats::record_match(args, Path::new(path), &match_data) {
+                console!("Could not update stats database '{path}': {e}");
+            }
+        }
+
+        if let Some(pipe) = &args.pipe {
+            // Pipes can't be renamed onto, so they are written in place. Opening one waits
+            // for a reader
+            let result = fs::OpenOptions::new()
+                .append(true)
+                .open(pipe)
+                .and_then(|mut f| writeln!(f, "{}", new_parts.join("\n")));
+            if let Err(e) = result {
+                console!("Could not write match to pipe '{pipe}': {e}");
+            }
+        }
+
+        if !missing.is_empty() {
+            let path = output_dir_path
+                .as_ref()
+                .join(format!("{timestamp}.incomplete"));
+            fs::write(&path, missing.join("\n"))?;
+            console!(
+                "Match is missing required values, listed in '{}'",
+                path.to_string_lossy()
+            );
+        }
+
+        if let Some(command) = &args.on_new_match {
+            run_on_new_match(command, &saved_paths[0]);
+        }
+
+        return Ok(Extraction::Saved {
+            path: saved_paths.swap_remove(0),
+            teams,
+            players,
+        });
+    }
+
+    // Temporary files are named per process, so don't leave one behind for every duplicate
+    let _ = fs::remove_file(&output_file_path);
+
+    Ok(Extraction::Duplicate { teams, players })
+}
+
+/// Lists, and optionally extracts player data from, the 'attributes.xml' files cached by
+/// Steam Cloud
+fn backfill_from_steam_cloud(args: &Args, output_dir_path: &Path) -> Result<(), Box<dyn Error>> {
+    let files = config::find_steam_cloud_attributes_files();
+    if files.is_empty() {
+        println!("Could not find any 'attributes.xml' files cached by Steam Cloud");
+    }
+    for path in files {
+        println!("{}", path.to_string_lossy());
+        if args.backfill_process {
+            let bytes = fs::read(&path)?;
+            let extraction = process_attributes(args, &bytes, output_dir_path);
+            dump_if_no_match(args, &bytes, &extraction);
+            if let Err(e) = extraction {
+                println!("Could not extract player data: {e}");
+            }
+        }
+    }
+    Ok(())
+}
+
+fn is_zip_archive(input: &str) -> bool {
+    Path::new(input)
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("zip"))
+}
+
+/// Extracts player data from every 'attributes.xml' in a zip archive, each into a
+/// subdirectory of the output directory matching its folder in the archive
+fn extract_archive(args: &Args, output_dir_path: &Path) -> Result<(), ExtractError> {
+    let mut archive = zip::ZipArchive::new(fs::File::open(&args.input)?)?;
+    let is_attributes = |p: &Path| p.file_name().is_some_and(|n| n == "attributes.xml");
+
+    let mut entries = Vec::new();
+    for i in 0..archive.len() {
+        match archive.by_index(i)?.enclosed_name() {
+            Some(p) if is_attributes(p) => entries.push((i, p.to_path_buf())),
+            _ => {}
+        }
+    }
+
+    let mut progress = progress::Progress::new("Extracting", entries.len() as u64);
+    for (i, entry_path) in entries {
+        progress.println(&format!("Processing '{}'", entry_path.to_string_lossy()));
+        let mut bytes = Vec::new();
+        // A bad entry is logged and the rest of the archive still extracted
+        let result = archive
+            .by_index(i)
+            .map_err(ExtractError::from)
+            .and_then(|mut entry| Ok(entry.read_to_end(&mut bytes)?))
+            .and_then(|_| {
+                process_attributes(
+                    args,
+                    &bytes,
+                    output_dir_path.join(entry_path.parent().unwrap()),
+                )
+            });
+        dump_if_no_match(args, &bytes, &result);
+        if let Err(e) = result {
+            progress.println(&format!(
+                "Could not extract player data from '{}': {e}",
+                entry_path.to_string_lossy()
+            ));
+        }
+        progress.inc();
     }
+    progress.finish();
 
     Ok(())
 }
+
+/// Directory of matches that failed to post to '--post-url'
+fn spool_dir(args: &Args, output_dir_path: &Path) -> Option<PathBuf> {
+    match &args.spool_dir {
+        Some(p) => Some(PathBuf::from(p)),
+        None if args.no_local => None,
+        None => Some(output_dir_path.join("spool")),
+    }
+}
+
+/// Copies a new match to the system clipboard for '--clipboard'
+fn copy_to_clipboard(text: &str) {
+    match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text)) {
+        Ok(()) => console!("Copied match to the clipboard"),
+        Err(e) => console!("Could not copy match to the clipboard: {e}"),
+    }
+}
+
+/// Number of 'Attr' items in an attributes file, or None if it is not valid XML
+fn attribute_count(args: &Args, bytes: &[u8]) -> Option<usize> {
+    let encoding = Encoding::for_label(args.encoding.as_bytes()).unwrap();
+    let (contents, _, _) = encoding.decode(bytes);
+    from_str::<Attributes>(&contents)
+        .ok()
+        .map(|attributes| attributes.items.len())
+}
+
+/// Sends a new match to '--post-url' and any '--sse-port' clients for '--no-local', keeping
+/// the last match sent in memory instead of comparing against saved files
+fn relay_match(args: &Args, match_data: Match) -> Extraction {
+    static LAST_RELAYED: Mutex<Option<Match>> = Mutex::new(None);
+
+    let teams = match_data.team_sizes.len();
+    let players = match_data.rows.len();
+    let mut last_relayed = LAST_RELAYED.lock().unwrap();
+    let is_duplicate = !args.no_dedup
+        && last_relayed
+            .as_ref()
+            .is_some_and(|last| match args.dedup_key {
+                DedupKey::Content => render_json(args, last) == render_json(args, &match_data),
+                DedupKey::Lobby => {
+                    let last = SavedMatch::parse(&render_csv(args, last));
+                    let new = SavedMatch::parse(&render_csv(args, &match_data));
+                    lobby_key(args, &last) == lobby_key(args, &new)
+                }
+            });
+    if is_duplicate {
+        return Extraction::Duplicate { teams, players };
+    }
+
+    let text = args.format[0].render(args, &match_data);
+    if args.brief {
+        console!("{}", brief_summary(args, &match_data));
+    } else {
+        console!("{text}");
+    }
+    if args.clipboard {
+        copy_to_clipboard(&text);
+    }
+
+    let json = render_json(args, &match_data);
+    if let Some(url) = &args.post_url {
+        let spool_dir = args.spool_dir.as_ref().map(Path::new);
+        let name = match_data.timestamp.as_deref().unwrap_or_default();
+        sink::post_match(url, spool_dir, name, &json);
+    }
+    if args.sse_port.is_some() {
+        sse::broadcast(&json);
+    }
+
+    *last_relayed = Some(match_data);
+    Extraction::Relayed { teams, players }
+}
+
+/// Finds the most recently modified output file with the given extension
+fn latest_output_file<P: AsRef<Path>>(
+    args: &Args,
+    output_dir_path: P,
+    extension: &str,
+) -> std::io::Result<Option<PathBuf>> {
+    let mut existing_files: Vec<fs::DirEntry> = fs::read_dir(&output_dir_path)?
+        .filter(|r| match r {
+            Ok(de) => {
+                // Stray files without an extension, e.g. editor backups, are skipped
+                de.metadata().is_ok_and(|m| m.is_file())
+                    && de.path().extension().and_then(|e| e.to_str()) == Some(extension)
+                    && !is_temp_file(args, &de.path())
+                    && de.path().file_stem().is_some_and(|s| s != LATEST_LINK_STEM)
+                    && !is_companion_file(&de.path())
+            }
+            _ => false,
+        })
+        .flatten()
+        .collect();
+    match args.latest_by {
+        // Files whose modification time can't be read are never considered the latest
+        LatestBy::Mtime => {
+            existing_files.sort_by_cached_key(|f| f.metadata().and_then(|m| m.modified()).ok())
+        }
+        LatestBy::Filename => {
+            // Files without a timestamped name are never considered the latest
+            existing_files.retain(|f| file_timestamp(&f.path()).is_some());
+            existing_files.sort_by_cached_key(|f| file_timestamp(&f.path()));
+        }
+    }
+    Ok(existing_files.last().map(|de| de.path()))
+}
+
+/// Points 'latest.<ext>' in the same directory at `path`, using a copy where symlinks are
+/// unavailable, e.g. without privileges on Windows
+fn update_latest_link(path: &Path) -> std::io::Result<()> {
+    let link = path.with_file_name(format!(
+        "{LATEST_LINK_STEM}.{}",
+        path.extension().unwrap().to_string_lossy()
+    ));
+    if fs::symlink_metadata(&link).is_ok() {
+        fs::remove_file(&link)?;
+    }
+
+    #[cfg(unix)]
+    let result = std::os::unix::fs::symlink(path.file_name().unwrap(), &link);
+    #[cfg(windows)]
+    let result = std::os::windows::fs::symlink_file(path.file_name().unwrap(), &link);
+    #[cfg(not(any(unix, windows)))]
+    let result: std::io::Result<()> = Err(ErrorKind::Unsupported.into());
+
+    result.or_else(|_| fs::copy(path, &link).map(|_| ()))
+}
+
+/// Name of the temporary output file used by this instance
+fn temp_file_name(args: &Args) -> String {
+    match &args.temp_file {
+        Some(name) => name.clone(),
+        None => format!("TEMP.{}.CSV", std::process::id()),
+    }
+}
+
+/// Whether a file is the temporary output file of this or, with the default name, any other
+/// instance
+fn is_temp_file(args: &Args, path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    name == temp_file_name(args) || temp_file_pid(path).is_some()
+}
+
+/// Process ID in the default name of a temporary output file
+fn temp_file_pid(path: &Path) -> Option<u32> {
+    path.file_name()?
+        .to_str()?
+        .strip_prefix("TEMP.")?
+        .strip_suffix(".CSV")?
+        .parse()
+        .ok()
+}
+
+/// Whether a process is still running, assuming it is where that can't be checked
+fn is_process_running(pid: u32) -> bool {
+    #[cfg(target_os = "linux")]
+    return Path::new("/proc").join(pid.to_string()).exists();
+
+    #[cfg(all(unix, not(target_os = "linux")))]
+    return Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(Stdio::null())
+        .status()
+        .map_or(true, |status| status.success());
+
+    #[cfg(windows)]
+    return Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {pid}"), "/NH"])
+        .output()
+        .map_or(true, |output| {
+            String::from_utf8_lossy(&output.stdout).contains(&pid.to_string())
+        });
+
+    #[cfg(not(any(unix, windows)))]
+    true
+}
+
+/// Appends your KDA for a match to '--kda-ledger', starting the file with a header row
+fn append_kda(
+    args: &Args,
+    path: &Path,
+    timestamp: &str,
+    match_data: &Match,
+) -> std::io::Result<()> {
+    let row = match match_data
+        .rows
+        .iter()
+        .find(|row| derived::is_mine(args, match_data, row))
+    {
+        Some(row) => row,
+        None => {
+            console!("'--my-profile-id' is not in this match, so it has no KDA");
+            return Ok(());
+        }
+    };
+
+    let is_new = !path.exists();
+    let mut ledger = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    if is_new {
+        writeln!(ledger, "match_timestamp,my_kda")?;
+    }
+    writeln!(
+        ledger,
+        "{timestamp},{}",
+        derived::float(args, derived::my_kda(match_data, row))
+    )
+}
+
+/// Saves the raw contents of an 'attributes.xml' for '--dump-on-empty' if it had no team data
+fn dump_if_no_match(args: &Args, bytes: &[u8], extraction: &Result<Extraction, ExtractError>) {
+    if let (Some(dir), Ok(Extraction::NoMatch)) = (&args.dump_on_empty, extraction) {
+        dump_attributes(Path::new(dir), bytes);
+    }
+}
+
+/// Saves the raw contents of an 'attributes.xml' without team data for debugging
+fn dump_attributes(dir: &Path, bytes: &[u8]) {
+    let path = dir.join(format!(
+        "attributes_{}.xml",
+        Local::now().format(TIMESTAMP_FORMAT)
+    ));
+    match fs::create_dir_all(dir).and_then(|_| fs::write(&path, bytes)) {
+        Ok(()) => console!(
+            "No team data found, saved 'attributes.xml' to '{}'",
+            path.to_string_lossy()
+        ),
+        Err(e) => console!("Could not save 'attributes.xml' without team data: {e}"),
+    }
+}
+
+/// Removes leftover temporary output files from the output directory, leaving those of other
+/// instances that are still running
+fn prune_temp_files(args: &Args, output_dir_path: &Path) {
+    let entries = match fs::read_dir(output_dir_path) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for path in entries.flatten().map(|de| de.path()) {
+        let is_leftover = match temp_file_pid(&path) {
+            Some(pid) => !is_process_running(pid),
+            None => is_temp_file(args, &path),
+        };
+        if path.is_file() && is_leftover {
+            match fs::remove_file(&path) {
+                Ok(()) => console!("Removed temporary file '{}'", path.to_string_lossy()),
+                Err(e) => console!(
+                    "Could not remove temporary file '{}': {e}",
+                    path.to_string_lossy()
+                ),
+            }
+        }
+    }
+}
+
+/// Whether a file was written alongside a match by '--split-teams' or '--events'
+fn is_companion_file(path: &Path) -> bool {
+    Path::new(path.file_stem().unwrap_or_default())
+        .extension()
+        .is_some_and(|e| {
+            SPLIT_TEAM_EXTENSIONS
+                .iter()
+                .chain([&EVENTS_EXTENSION])
+                .any(|s| e == *s)
+        })
+}
+
+/// The current time as an output file timestamp, moved on to the next second without any saved
+/// files if a match was already saved this second
+fn free_timestamp(output_dir_path: &Path) -> String {
+    let taken: BTreeSet<NaiveDateTime> = fs::read_dir(output_dir_path)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|de| file_timestamp(&de.path()))
+        .collect();
+    let mut time = Local::now().naive_local().with_nanosecond(0).unwrap();
+    while taken.contains(&time) {
+        time += chrono::Duration::seconds(1);
+    }
+    time.format(TIMESTAMP_FORMAT).to_string()
+}
+
+/// Parses the timestamp from the name of an output file, e.g. '2023-02-10_21-04-33.csv'
+fn file_timestamp(path: &Path) -> Option<NaiveDateTime> {
+    let stem = path.file_stem()?.to_str()?;
+    let stem = split_part_number(stem).map_or(stem, |(base, _)| base);
+    NaiveDateTime::parse_from_str(stem, TIMESTAMP_FORMAT).ok()
+}
+
+/// Splits '<timestamp>_<part>' file stems written by '--rows-per-file', or
+/// '<timestamp>_team<team>' ones written by '--per-team-files', into the timestamp and part
+/// or team number
+fn split_part_number(stem: &str) -> Option<(&str, u64)> {
+    let (base, part) = stem.rsplit_once('_')?;
+    let part = part.strip_prefix("team").unwrap_or(part).parse().ok()?;
+    NaiveDateTime::parse_from_str(base, TIMESTAMP_FORMAT).ok()?;
+    Some((base, part))
+}
+
+/// Paths of the output files for the parts of a match, given the suffix of each file stem
+fn output_part_paths(
+    output_dir_path: &Path,
+    timestamp: &str,
+    extension: &str,
+    suffixes: &[String],
+) -> Vec<PathBuf> {
+    suffixes
+        .iter()
+        .map(|suffix| output_dir_path.join(format!("{timestamp}{suffix}.{extension}")))
+        .collect()
+}
+
+/// Reads an output file, along with the other parts of its match if it was split across
+/// several files
+fn read_output_parts(path: &Path) -> std::io::Result<Vec<String>> {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let base = match split_part_number(&stem) {
+        Some((base, _)) => base,
+        None => return Ok(vec![fs::read_to_string(path)?]),
+    };
+
+    let mut parts: Vec<(u64, PathBuf)> = fs::read_dir(path.parent().unwrap())?
+        .flatten()
+        .map(|de| de.path())
+        .filter(|p| p.extension() == path.extension())
+        .filter_map(|p| {
+            let stem = p.file_stem()?.to_str()?;
+            let (part_base, part) = split_part_number(stem)?;
+            (part_base == base).then_some((part, p.clone()))
+        })
+        .collect();
+    parts.sort();
+    parts
+        .into_iter()
+        .map(|(_, p)| fs::read_to_string(p))
+        .collect()
+}
+
+/// Reads every part of a saved CSV match as a single match
+fn read_saved_parts(path: &Path) -> std::io::Result<SavedMatch> {
+    Ok(read_output_parts(path)?
+        .iter()
+        .map(|part| SavedMatch::parse(part))
+        .reduce(|mut saved, part| {
+            saved.rows.extend(part.rows);
+            saved
+        })
+        .unwrap_or_else(|| SavedMatch::parse("")))
+}
+
+/// Profile IDs, names and teams of the players outside of your own team, for '--dedup-key
+/// lobby'
+fn lobby_key<'a>(args: &Args, saved: &'a SavedMatch) -> BTreeSet<(&'a str, &'a str, &'a str)> {
+    let value = |row: &'a [String], column| saved.value(row, column).unwrap_or_default();
+    let my_team = saved
+        .rows
+        .iter()
+        .find(|row| args.my_profile_id.as_deref() == Some(value(row, "profileid")))
+        .map(|row| value(row, "Team"));
+
+    saved
+        .rows
+        .iter()
+        .filter(|row| Some(value(row, "Team")) != my_team)
+        .map(|row| {
+            (
+                value(row, "profileid"),
+                value(row, "blood_line_name"),
+                value(row, "Team"),
+            )
+        })
+        .collect()
+}
+
+/// Output files of the latest match, with their modification times, and the contents of its
+/// parts, so that continuous mode doesn't reread them for every comparison
+struct LatestParts {
+    files: Vec<(PathBuf, SystemTime)>,
+    parts: Vec<String>,
+}
+
+/// Set by '--json-result' before anything is printed
+static CONSOLE_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+static LATEST_PARTS: Mutex<Option<LatestParts>> = Mutex::new(None);
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Remembers the contents of the parts of a match that were just written to `paths`
+fn remember_latest_parts(paths: &[PathBuf], parts: Vec<String>) {
+    let files = paths
+        .iter()
+        .filter_map(|p| Some((p.clone(), modified(p)?)))
+        .collect();
+    *LATEST_PARTS.lock().unwrap() = Some(LatestParts { files, parts });
+}
+
+/// Like `read_output_parts`, but using the remembered contents if `path` is one of their
+/// files and hasn't been modified since
+fn read_latest_parts(path: &Path) -> std::io::Result<Vec<String>> {
+    if let Some(latest) = &*LATEST_PARTS.lock().unwrap() {
+        let is_unchanged = latest
+            .files
+            .iter()
+            .any(|(p, time)| p == path && modified(p) == Some(*time));
+        if is_unchanged {
+            return Ok(latest.parts.clone());
+        }
+    }
+
+    let parts = read_output_parts(path)?;
+    remember_latest_parts(&[path.to_path_buf()], parts.clone());
+    Ok(parts)
+}
+
+/// Builds a command running `command` in the platform's shell
+fn shell_command(command: &str) -> Command {
+    #[cfg(windows)]
+    let mut shell = Command::new("cmd");
+    #[cfg(windows)]
+    shell.arg("/C");
+    #[cfg(not(windows))]
+    let mut shell = Command::new("sh");
+    #[cfg(not(windows))]
+    shell.arg("-c");
+
+    shell.arg(command);
+    shell
+}
+
+/// Runs '--on-new-match' for a newly saved match, logging rather than returning any failure
+fn run_on_new_match(command: &str, path: &Path) {
+    let mut command_line = shell_command(command);
+    command_line.env("HUNT_MATCH_FILE", path);
+    // Its output is console output like ours
+    if CONSOLE_TO_STDERR.load(Ordering::Relaxed) {
+        command_line.stdout(std::io::stderr());
+    }
+    match command_line.status() {
+        Ok(status) if status.success() => {}
+        Ok(status) => console!("'--on-new-match' command '{command}' failed: {status}"),
+        Err(e) => console!("Could not run '--on-new-match' command '{command}': {e}"),
+    }
+}
+
+/// Pipes every attribute as a JSON object of names to values into a shell command, returning
+/// its output
+fn run_exec(args: &Args, command: &str, xml: &str) -> Result<String, ExtractError> {
+    let attributes = read_attributes(args, xml)?;
+    let attr_map: serde_json::Map<String, serde_json::Value> = attributes
+        .into_iter()
+        .map(|item| (item.name, item.value.into()))
+        .collect();
+
+    let mut child = shell_command(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| ExtractError::Exec {
+            command: command.to_string(),
+            reason: e.to_string(),
+        })?;
+    // Written from another thread, as a command that answers while still reading would
+    // otherwise block on a full stdout pipe
+    let json = serde_json::Value::from(attr_map).to_string();
+    let mut stdin = child.stdin.take().unwrap();
+    let (written, output) = std::thread::scope(|s| {
+        let writer = s.spawn(move || stdin.write_all(json.as_bytes()));
+        let output = child.wait_with_output();
+        (writer.join().unwrap(), output)
+    });
+    let output = output?;
+
+    if !output.status.success() {
+        return Err(ExtractError::Exec {
+            command: command.to_string(),
+            reason: format!(
+                "{}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        });
+    }
+
+    written?;
+
+    String::from_utf8(output.stdout).map_err(|_| ExtractError::Exec {
+        command: command.to_string(),
+        reason: "output is not valid UTF-8".to_string(),
+    })
+}
+
+/// Renames a file, falling back to copying and deleting it if the destination is on a
+/// different filesystem
+fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    match fs::rename(from, to) {
+        Err(e) if e.kind() == ErrorKind::CrossesDevices => {
+            console!(
+                "Could not rename '{}' across filesystems, copying instead",
+                from.to_string_lossy()
+            );
+            fs::copy(from, to)?;
+            fs::remove_file(from)
+        }
+        result => result,
+    }
+}
+
+/// Parses the attributes from 'attributes.xml', dropping any player attributes beyond the
+/// declared teams and team sizes under '--clear-stale'
+fn read_attributes(args: &Args, xml: &str) -> Result<Vec<Item>, ExtractError> {
+    let start = Instant::now();
+    let attributes: Attributes = from_str(xml)?;
+    timings::record("XML parse", start);
+    if !args.clear_stale {
+        return Ok(attributes.items);
+    }
+
+    let values: HashMap<&str, &str> = attributes
+        .items
+        .iter()
+        .map(|item| (item.name.as_str(), item.value.as_str()))
+        .collect();
+    let count = |key: &str| values.get(key).and_then(|v| v.parse::<u32>().ok());
+    let team_sizes: Vec<u32> = (0..count(&args.team_count_attr).unwrap_or(0))
+        .map(|team| count(&args.team_size_attr.replace("{team}", &team.to_string())).unwrap_or(0))
+        .collect();
+
+    let is_current: Vec<bool> = attributes
+        .items
+        .iter()
+        .map(|item| match player_slot(&item.name) {
+            Some((team, player, _)) => team_sizes
+                .get(team as usize)
+                .is_some_and(|&team_size| player < team_size),
+            None => true,
+        })
+        .collect();
+
+    let stale = is_current.iter().filter(|&&current| !current).count();
+    if stale > 0 {
+        console!("Ignoring {stale} stale player attributes");
+    }
+
+    Ok(attributes
+        .items
+        .into_iter()
+        .zip(is_current)
+        .filter_map(|(item, current)| current.then_some(item))
+        .collect())
+}
+
+/// The format '--output-file' is written in, which replaces '--format', from its extension
+fn output_file_format(args: &Args) -> Option<Format> {
+    let extension = Path::new(args.output_file.as_ref()?).extension()?;
+    Format::from_extension(&extension.to_string_lossy())
+}
+
+/// Parses the contents of 'attributes.xml' into one row per player, or `None` if the file
+/// has no team data
+fn parse_match(args: &Args, xml: &str) -> Result<Option<Match>, ExtractError> {
+    let attributes = read_attributes(args, xml)?;
+    let start = Instant::now();
+    let writes_raw = match output_file_format(args) {
+        Some(format) => format == Format::Raw,
+        None => args.format.contains(&Format::Raw),
+    };
+    let raw_attributes = if writes_raw {
+        attributes
+            .iter()
+            .map(|item| (item.name.clone(), item.value.clone()))
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    // Build map of names to values from attributes file
+    let mut attr_map = HashMap::new();
+    for item in attributes.iter() {
+        attr_map.insert(&item.name, &item.value);
+    }
+
+    // Check if attributes file has team data, and get the number of teams
+    let num_teams = match attr_map.get(&args.team_count_attr) {
+        Some(num_teams) => parse_team_count(&args.team_count_attr, num_teams)?,
+        None => return Ok(None),
+    };
+
+    // Get number of players in each team
+    let mut num_players = Vec::new();
+    for team in 0..num_teams {
+        let key = args.team_size_attr.replace("{team}", &team.to_string());
+        match attr_map.get(&key) {
+            Some(team_size) => num_players.push(parse_team_count(&key, team_size)?),
+            None => return Err(ExtractError::MissingAttribute { key }),
+        }
+    }
+
+    // Leftover player slots from a previous, larger match indicate stale data
+    if args.strict {
+        for (team, player, _) in attr_map.keys().filter_map(|k| player_slot(k)) {
+            match num_players.get(team as usize) {
+                Some(&team_size) if player >= team_size => {
+                    return Err(ExtractError::StaleAttributes {
+                        team,
+                        player,
+                        team_size,
+                    });
+                }
+                Some(_) => {}
+                None => return Err(ExtractError::StaleTeam { team, num_teams }),
+            }
+        }
+    }
+
+    // Iterate over players in each team, collecting attributes that exist in HEADERS array
+    let mut rows = Vec::new();
+    for (team, &team_size) in num_players.iter().enumerate() {
+        for player in 0..team_size {
+            let values = HEADERS
+                .iter()
+                .map(|header| {
+                    let key = format!("MissionBagPlayer_{team}_{player}_{header}");
+                    match attr_map.get(&key) {
+                        Some(value) => Ok(value.to_string()),
+                        None => Err(ExtractError::MissingAttribute { key }),
+                    }
+                })
+                .collect::<Result<_, _>>()?;
+
+            rows.push(PlayerRow {
+                team: team as u32,
+                player,
+                values,
+            });
+        }
+    }
+
+    let mut match_data = Match {
+        team_sizes: num_players,
+        team_numbers: Vec::new(),
+        columns: HEADERS.iter().map(|h| h.to_string()).collect(),
+        rows,
+        timestamp: None,
+        attributes: raw_attributes,
+    };
+
+    // Players without a profile ID are left over from a partly written or stale file
+    let declared: u32 = match_data.team_sizes.iter().sum();
+    let found = match_data
+        .rows
+        .iter()
+        .filter(|row| {
+            match_data
+                .value(row, "profileid")
+                .is_some_and(|id| !id.is_empty())
+        })
+        .count() as u32;
+    if found != declared {
+        if args.strict {
+            return Err(ExtractError::MissingPlayers { found, declared });
+        }
+        console!(
+            "{}",
+            paint(
+                args.color.enabled(),
+                &format!(
+                    "Warning: only {found} of the {declared} players declared by the teams have \
+                    a profile ID, the match may be incomplete"
+                ),
+                AnsiColors::Red
+            )
+        );
+    }
+
+    // No hunter can be in two teams, so this is also stale data mixed into the match
+    let offset = if args.zero_based { 0 } else { 1 };
+    let mut teams_by_id: BTreeMap<&str, BTreeSet<u32>> = BTreeMap::new();
+    for row in &match_data.rows {
+        match match_data.value(row, "profileid") {
+            Some(id) if !id.is_empty() => {
+                teams_by_id.entry(id).or_default().insert(row.team + offset);
+            }
+            _ => {}
+        }
+    }
+    for (id, teams) in teams_by_id.iter().filter(|(_, teams)| teams.len() > 1) {
+        let teams = teams
+            .iter()
+            .map(|t| t.to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+        if args.strict {
+            return Err(ExtractError::DuplicateProfileId {
+                id: id.to_string(),
+                teams,
+            });
+        }
+        console!(
+            "{}",
+            paint(
+                args.color.enabled(),
+                &format!(
+                    "Warning: profile ID '{id}' is in more than one team ({teams}), the match \
+                    may include stale data"
+                ),
+                AnsiColors::Red
+            )
+        );
+    }
+
+    if let Some(clamp) = args.clamp_negatives {
+        clamp_negatives(clamp, &mut match_data);
+    }
+
+    if args.anonymize {
+        anonymize(args, &mut match_data);
+    }
+
+    add_extra_columns(args, &attr_map, &mut match_data);
+
+    if args.enemies_only {
+        remove_my_team(args, &mut match_data);
+    }
+
+    if args.compact_empty_teams {
+        compact_empty_teams(args, &mut match_data);
+    }
+
+    timings::record("Attribute mapping", start);
+
+    Ok(Some(match_data))
+}
+
+/// Describes each '--require' column without a value, e.g. "team 2 player 1 'mmr'"
+fn missing_required(args: &Args, match_data: &Match) -> Vec<String> {
+    let offset = if args.zero_based { 0 } else { 1 };
+    let mut missing = Vec::new();
+    for row in &match_data.rows {
+        for column in &args.require {
+            if match_data.value(row, column).is_none_or(str::is_empty) {
+                missing.push(format!(
+                    "team {} player {} '{column}'",
+                    match_data.team_output(args, row.team),
+                    row.player + offset
+                ));
+            }
+        }
+    }
+    missing
+}
+
+/// Removes the rows of your own team, leaving it empty
+fn remove_my_team(args: &Args, match_data: &mut Match) {
+    let my_team = match_data
+        .rows
+        .iter()
+        .find(|row| match_data.value(row, "profileid") == args.my_profile_id.as_deref())
+        .map(|row| row.team);
+    match my_team {
+        Some(team) => {
+            match_data.rows.retain(|row| row.team != team);
+            match_data.team_sizes[team as usize] = 0;
+        }
+        None => console!("'--my-profile-id' is not in this match, so every team is an enemy"),
+    }
+}
+
+/// Drops teams without players, optionally renumbering the remaining teams consecutively
+fn compact_empty_teams(args: &Args, match_data: &mut Match) {
+    let mut new_indices = Vec::new();
+    let mut kept = Vec::new();
+    for (team, &team_size) in match_data.team_sizes.iter().enumerate() {
+        new_indices.push(kept.len() as u32);
+        if team_size > 0 {
+            kept.push(team as u32);
+        }
+    }
+
+    // Rows always refer to teams by their index in `team_sizes`, so only the numbers written
+    // keep any gaps
+    if !args.renumber {
+        match_data.team_numbers = kept
+            .iter()
+            .map(|&team| {
+                let numbers = &match_data.team_numbers;
+                numbers.get(team as usize).copied().unwrap_or(team)
+            })
+            .collect();
+    }
+    match_data.team_sizes.retain(|&team_size| team_size > 0);
+    for row in match_data.rows.iter_mut() {
+        row.team = new_indices[row.team as usize];
+    }
+}
+
+/// Appends the match-level and derived columns enabled by `args`
+fn add_extra_columns(args: &Args, attr_map: &HashMap<&String, &String>, match_data: &mut Match) {
+    if args.seat {
+        let offset = if args.zero_based { 0 } else { 1 };
+        let seats: HashMap<(u32, u32), usize> = match_data
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(seat, row)| ((row.team, row.player), seat + offset))
+            .collect();
+        match_data.push_column("seat", |_, row| seats[&(row.team, row.player)].to_string());
+    }
+
+    if args.region {
+        let region = attr_map
+            .get(&args.region_attr)
+            .map(|r| r.to_string())
+            .unwrap_or_default();
+        match_data.push_column("region", |_, _| region.clone());
+    }
+
+    if args.bosses {
+        for (index, boss) in BOSSES.iter().enumerate() {
+            let present = attr_map
+                .get(&format!("{}{index}", args.boss_attr_prefix))
+                .map(|v| v.to_string())
+                .unwrap_or_default();
+            match_data.push_column(&format!("boss_{boss}"), |_, _| present.clone());
+        }
+    }
+
+    if args.team_composition {
+        let team_attr = |team: u32, name: &str| {
+            attr_map
+                .get(&format!("MissionBagTeam_{team}_{name}"))
+                .map(|v| v.as_str())
+        };
+        // Solo teams are neither invited nor random
+        match_data.push_column("is_random", |m, row| {
+            (m.team_sizes[row.team as usize] > 1
+                && team_attr(row.team, "isinvite") == Some("false"))
+            .to_string()
+        });
+        match_data.push_column("is_partner", |m, row| {
+            let is_me = args.my_profile_id.is_some()
+                && m.value(row, "profileid") == args.my_profile_id.as_deref();
+            (team_attr(row.team, "ownteam") == Some("true") && !is_me).to_string()
+        });
+    }
+
+    if args.team_won {
+        match_data.push_column("team_won", |m, row| {
+            let team_attr = args
+                .team_won_attr
+                .as_ref()
+                .and_then(|attr| attr_map.get(&attr.replace("{team}", &row.team.to_string())));
+            match team_attr {
+                Some(value) => derived::number(Some(value)) > 0,
+                None => m.rows.iter().any(|other| {
+                    other.team == row.team && derived::number(m.value(other, "teamextraction")) > 0
+                }),
+            }
+            .to_string()
+        });
+    }
+
+    // Any proximity attributes beyond the standard ones, e.g. from newer game versions
+    if args.proximity_detail {
+        let suffixes: BTreeSet<&str> = attr_map
+            .keys()
+            .filter_map(|k| player_slot(k))
+            .map(|(_, _, suffix)| suffix)
+            .filter(|suffix| suffix.starts_with("proximity") && !HEADERS.contains(suffix))
+            .collect();
+        for suffix in suffixes {
+            match_data.push_column(suffix, |_, row| {
+                attr_map
+                    .get(&format!(
+                        "MissionBagPlayer_{}_{}_{suffix}",
+                        row.team, row.player
+                    ))
+                    .map(|v| v.to_string())
+                    .unwrap_or_default()
+            });
+        }
+    }
+
+    derived::add_derived_columns(args, match_data);
+}
+
+/// Replaces names and profile IDs, except for `--my-profile-id`, with tokens that are
+/// consistent for the rest of the run
+fn anonymize(args: &Args, match_data: &mut Match) {
+    static SALT: OnceLock<u64> = OnceLock::new();
+    let salt = *SALT.get_or_init(|| {
+        config::anonymize_salt().unwrap_or_else(|e| {
+            console!(
+                "Could not load the '--anonymize' salt, so tokens will differ between runs: \
+                {e}"
+            );
+            RandomState::new().hash_one(SystemTime::now())
+        })
+    });
+
+    if let (Some(id_index), Some(name_index)) = (
+        match_data.column_index("profileid"),
+        match_data.column_index("blood_line_name"),
+    ) {
+        for row in match_data.rows.iter_mut() {
+            if args.my_profile_id.as_ref() == Some(&row.values[id_index]) {
+                continue;
+            }
+
+            let token = anonymize_token(salt, &row.values[id_index]);
+            row.values[name_index] = format!("Hunter {}", &token[..8]);
+            row.values[id_index] = token;
+        }
+    }
+
+    // The same for every player in the attributes kept for '--format raw', including stale
+    // players left out of the rows
+    let tokens: HashMap<String, String> = match_data
+        .attributes
+        .iter()
+        .filter_map(|(name, value)| {
+            let prefix = name.strip_suffix("profileid")?;
+            (prefix.starts_with("MissionBagPlayer_") && args.my_profile_id.as_ref() != Some(value))
+                .then(|| (prefix.to_string(), anonymize_token(salt, value)))
+        })
+        .collect();
+    for (name, value) in match_data.attributes.iter_mut() {
+        let token = |suffix| tokens.get(name.strip_suffix(suffix)?);
+        if let Some(token) = token("profileid") {
+            *value = token.clone();
+        } else if let Some(token) = token("blood_line_name") {
+            *value = format!("Hunter {}", &token[..8]);
+        }
+    }
+}
+
+/// Token replacing a profile ID, from a 64-bit FNV-1a hash of the salt and the ID, which
+/// unlike std's hashers is the same in every build
+fn anonymize_token(salt: u64, profile_id: &str) -> String {
+    let hash = salt
+        .to_le_bytes()
+        .iter()
+        .chain(profile_id.as_bytes())
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, &byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+    format!("{hash:016x}")
+}
+
+fn clamp_negatives(clamp: Clamp, match_data: &mut Match) {
+    let indices: Vec<usize> = COUNTERS
+        .iter()
+        .filter_map(|column| match_data.column_index(column))
+        .collect();
+
+    for row in match_data.rows.iter_mut() {
+        for &index in &indices {
+            if row.values[index].parse::<i64>().is_ok_and(|v| v < 0) {
+                row.values[index] = match clamp {
+                    Clamp::Zero => "0".to_string(),
+                    Clamp::Empty => String::new(),
+                };
+            }
+        }
+    }
+}
+
+fn parse_team_count(key: &str, value: &str) -> Result<u32, ExtractError> {
+    value.parse().map_err(|_| ExtractError::InvalidTeamCount {
+        key: key.to_string(),
+        value: value.to_string(),
+    })
+}
+
+/// Gets the zero-based team and player indices, and the attribute suffix, from a
+/// 'MissionBagPlayer_{team}_{player}_*' attribute name. The suffix is everything after the
+/// player index, so it may itself contain underscores and digits
+fn player_slot(name: &str) -> Option<(u32, u32, &str)> {
+    let mut parts = name.strip_prefix("MissionBagPlayer_")?.splitn(3, '_');
+    let team = parts.next()?.parse().ok()?;
+    let player = parts.next()?.parse().ok()?;
+    let suffix = parts.next().filter(|suffix| !suffix.is_empty())?;
+    Some((team, player, suffix))
+}
+
+/// Summarizes a match in a single line, e.g. '[14:32:05] 5 teams, 14 players, my MMR 3241,
+/// extracted'
+fn brief_summary(args: &Args, match_data: &Match) -> String {
+    let mut summary = format!(
+        "[{}] {} teams, {} players",
+        Local::now().format("%H:%M:%S"),
+        match_data.team_sizes.len(),
+        match_data.rows.len()
+    );
+
+    let my_row = match_data
+        .rows
+        .iter()
+        .find(|row| match_data.value(row, "profileid") == args.my_profile_id.as_deref());
+    if let Some(row) = my_row {
+        let mmr = match_data.value(row, "mmr").unwrap_or_default();
+        let extracted = derived::number(match_data.value(row, "teamextraction")) > 0;
+        let color = args.color.enabled();
+        summary.push_str(&format!(
+            ", my MMR {mmr}, {}",
+            if extracted {
+                paint(color, "extracted", AnsiColors::Green)
+            } else {
+                paint(color, "died", AnsiColors::Red)
+            }
+        ));
+    }
+
+    summary
+}
+
+/// Quotes a CSV field if it contains a comma
+fn csv_field(value: &str) -> Cow<'_, str> {
+    if value.contains(',') {
+        Cow::Owned(format!("\"{value}\""))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+/// Renders player rows as the contents of a CSV file
+fn render_csv(args: &Args, match_data: &Match) -> String {
+    let mut csv = String::new();
+
+    if args.bom {
+        csv.push(BOM);
+    }
+
+    if args.embed_version {
+        csv.push_str(&format!(
+            "# {} {}\n",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION")
+        ));
+    }
+
+    // Write CSV header row
+    let timestamp = match_data
+        .timestamp
+        .as_deref()
+        .filter(|_| args.include_timestamp_column);
+    if timestamp.is_some() {
+        csv.push_str("match_timestamp,");
+    }
+    csv.push_str(&format!("Team,Player,{}", match_data.columns.join(",")));
+
+    if args.with_descriptions {
+        let descriptions: Vec<Cow<str>> = timestamp
+            .map(|_| "match_timestamp")
+            .into_iter()
+            .chain(["Team", "Player"])
+            .chain(match_data.columns.iter().map(String::as_str))
+            .map(|column| csv_field(column_description(column)))
+            .collect();
+        csv.push_str(&format!("\n{}", descriptions.join(",")));
+    }
+
+    for row in &match_data.rows {
+        let team_output = match_data.team_output(args, row.team);
+        let player_output = row.player + if args.zero_based { 0 } else { 1 };
+        // Each row starts by ending the line before it, which is never empty, so only
+        // '--trailing-newline' ends the file with a newline
+        csv.push('\n');
+        if let Some(timestamp) = timestamp {
+            csv.push_str(&format!("{timestamp},"));
+        }
+        csv.push_str(&format!("{team_output},{player_output}"));
+
+        for value in &row.values {
+            let value = match value {
+                v if v.is_empty() => args.na_string.as_ref().unwrap_or(v),
+                v => v,
+            };
+
+            csv.push_str(&format!(",{}", csv_field(value)));
+        }
+    }
+
+    if args.trailing_newline {
+        csv.push('\n');
+    }
+
+    csv
+}
+
+/// Renders every attribute of the match as a 'name,value' CSV row, in the order of the file
+fn render_raw(match_data: &Match) -> String {
+    let mut csv = "name,value".to_string();
+    for (name, value) in &match_data.attributes {
+        csv.push_str(&format!("\n{name},{}", csv_field(value)));
+    }
+    csv
+}
+
+/// Renders the team sizes and player values of the match as an 'attributes.xml' file, which
+/// can be read back as input. Teams are numbered by their index, as the format has no gaps for
+/// teams left out by '--compact-empty-teams'
+fn render_xml(args: &Args, match_data: &Match) -> String {
+    let mut attributes = vec![(
+        args.team_count_attr.clone(),
+        match_data.team_sizes.len().to_string(),
+    )];
+    for (team, team_size) in match_data.team_sizes.iter().enumerate() {
+        attributes.push((
+            args.team_size_attr.replace("{team}", &team.to_string()),
+            team_size.to_string(),
+        ));
+    }
+    for row in &match_data.rows {
+        for header in HEADERS {
+            if let Some(value) = match_data.value(row, header) {
+                attributes.push((
+                    format!("MissionBagPlayer_{}_{}_{header}", row.team, row.player),
+                    value.to_string(),
+                ));
+            }
+        }
+    }
+
+    let mut xml = "<Attributes>".to_string();
+    for (name, value) in attributes {
+        xml.push_str(&format!(
+            "\n  <Attr name=\"{}\" value=\"{}\"/>",
+            escape(&name),
+            escape(&value)
+        ));
+    }
+    xml.push_str("\n</Attributes>");
+    xml
+}
+
+/// Renders a readable summary of each team and player, marking '--my-profile-id' with '>'
+fn render_report(args: &Args, match_data: &Match) -> String {
+    let mut report = format!(
+        "{} teams, {} players\n",
+        match_data.team_sizes.len(),
+        match_data.rows.len()
+    );
+
+    let mut current_team = None;
+    for row in &match_data.rows {
+        if current_team != Some(row.team) {
+            current_team = Some(row.team);
+            let team_size = match_data
+                .rows
+                .iter()
+                .filter(|r| r.team == row.team)
+                .count();
+            report.push_str(&format!(
+                "\nTeam {} ({team_size} {})\n",
+                match_data.team_output(args, row.team),
+                if team_size == 1 { "player" } else { "players" }
+            ));
+        }
+
+        let value = |column| match_data.value(row, column);
+        let (marker, score) = if derived::is_mine(args, match_data, row) {
+            let kda = derived::float(args, derived::my_kda(match_data, row));
+            (">", format!("KDA {kda}"))
+        } else {
+            let kills =
+                derived::number(value("killedme")) + derived::number(value("killedteammate"));
+            let deaths =
+                derived::number(value("killedbyme")) + derived::number(value("killedbyteammate"));
+            (" ", format!("K/D vs your team {kills}/{deaths}"))
+        };
+        report.push_str(&format!(
+            "{marker} {:<24} MMR {:>4}  {score}\n",
+            value("blood_line_name").unwrap_or_default(),
+            value("mmr").unwrap_or_default(),
+        ));
+    }
+
+    report
+}
+
+/// Whether a column's values are written as integers in JSON output
+fn is_integer_column(args: &Args, column: &str) -> bool {
+    args.proximity_detail && column.starts_with("proximity")
+}
+
+/// Describes the output of `render_json` as a JSON Schema, including the columns enabled by
+/// `args`
+fn json_schema(args: &Args) -> String {
+    let mut match_data = Match {
+        team_sizes: Vec::new(),
+        team_numbers: Vec::new(),
+        columns: HEADERS.iter().map(|h| h.to_string()).collect(),
+        rows: Vec::new(),
+        timestamp: None,
+        attributes: Vec::new(),
+    };
+    add_extra_columns(args, &HashMap::new(), &mut match_data);
+
+    let mut properties = serde_json::Map::new();
+    properties.insert("team".to_string(), json!({ "type": "integer" }));
+    properties.insert("player".to_string(), json!({ "type": "integer" }));
+    for column in &match_data.columns {
+        let column_type = if is_integer_column(args, column) {
+            "integer"
+        } else {
+            "string"
+        };
+        properties.insert(column.to_string(), json!({ "type": [column_type, "null"] }));
+    }
+    let required: Vec<&String> = properties.keys().collect();
+
+    let schema = json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "Hunt: Showdown match players",
+        "type": "array",
+        "items": {
+            "type": "object",
+            "properties": properties,
+            "required": required,
+        },
+    });
+
+    serde_json::to_string_pretty(&schema).unwrap()
+}
+
+/// A player row serialized as a JSON object, with keys in the same order as the columns
+struct JsonPlayer<'a> {
+    args: &'a Args,
+    match_data: &'a Match,
+    row: &'a PlayerRow,
+}
+
+impl Serialize for JsonPlayer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (args, row) = (self.args, self.row);
+        let mut map = serializer.serialize_map(Some(self.match_data.columns.len() + 2))?;
+        map.serialize_entry("team", &self.match_data.team_output(args, row.team))?;
+        map.serialize_entry(
+            "player",
+            &(row.player + if args.zero_based { 0 } else { 1 }),
+        )?;
+
+        for (column, value) in self.match_data.columns.iter().zip(&row.values) {
+            let value = match value {
+                v if v.is_empty() => serde_json::Value::Null,
+                v if is_integer_column(args, column) => match v.parse::<i64>() {
+                    Ok(n) => n.into(),
+                    Err(_) => v.as_str().into(),
+                },
+                v => v.as_str().into(),
+            };
+            map.serialize_entry(column, &value)?;
+        }
+
+        map.end()
+    }
+}
+
+/// Renders player rows as a JSON array of player objects, with empty values as null
+fn render_json(args: &Args, match_data: &Match) -> String {
+    let players: Vec<JsonPlayer> = match_data
+        .rows
+        .iter()
+        .map(|row| JsonPlayer {
+            args,
+            match_data,
+            row,
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&players).unwrap()
+}
+
+/// Renders player rows as newline-delimited JSON, one object per line
+fn render_ndjson(args: &Args, match_data: &Match) -> String {
+    let lines: Vec<String> = match_data
+        .rows
+        .iter()
+        .map(|row| {
+            serde_json::to_string(&JsonPlayer {
+                args,
+                match_data,
+                row,
+            })
+            .unwrap()
+        })
+        .collect();
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(options: &[&str]) -> Args {
+        Args::try_parse_from(["hunt-summary-extractor"].iter().chain(options)).unwrap()
+    }
+
+    /// An 'attributes.xml' with teams of the given sizes, every player value set, and any
+    /// extra attributes after them
+    fn attributes_xml(team_sizes: &[u32], extra: &[(&str, &str)]) -> String {
+        let mut attributes = vec![(
+            "MissionBagNumTeams".to_string(),
+            team_sizes.len().to_string(),
+        )];
+        for (team, &team_size) in (0u32..).zip(team_sizes) {
+            attributes.push((
+                format!("MissionBagTeam_{team}_numplayers"),
+                team_size.to_string(),
+            ));
+            for player in 0..team_size {
+                for header in HEADERS {
+                    let value = match *header {
+                        "blood_line_name" => format!("Hunter {team}{player}"),
+                        "mmr" => (2000 + 100 * team + 10 * player).to_string(),
+                        "profileid" => (1000 + 10 * team + player).to_string(),
+                        _ => "0".to_string(),
+                    };
+                    attributes.push((format!("MissionBagPlayer_{team}_{player}_{header}"), value));
+                }
+            }
+        }
+        attributes.extend(extra.iter().map(|&(n, v)| (n.to_string(), v.to_string())));
+
+        let mut xml = "<Attributes>\n".to_string();
+        for (name, value) in attributes {
+            xml.push_str(&format!("<Attr name=\"{name}\" value=\"{value}\"/>\n"));
+        }
+        xml.push_str("</Attributes>\n");
+        xml
+    }
+
+    fn parse(args: &Args, xml: &str) -> Match {
+        parse_match(args, xml).unwrap().unwrap()
+    }
+
+    #[test]
+    fn parses_and_renders_in_memory() {
+        let args = args(&[]);
+        let match_data = parse(&args, &attributes_xml(&[2, 1], &[]));
+        assert_eq!(match_data.team_sizes, vec![2, 1]);
+        assert_eq!(match_data.rows.len(), 3);
+
+        let csv = render_csv(&args, &match_data);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], format!("Team,Player,{}", HEADERS.join(",")));
+        assert!(lines[1].starts_with("1,1,Hunter 00,2000,"));
+        assert!(lines[2].starts_with("1,2,Hunter 01,2010,"));
+        assert!(lines[3].starts_with("2,1,Hunter 10,2100,"));
+        assert!(lines[3].ends_with(",1010"));
+    }
+
+    #[test]
+    fn file_without_team_data_is_no_match() {
+        let xml = "<Attributes><Attr name=\"UIMemory\" value=\"1\"/></Attributes>";
+        assert!(parse_match(&args(&[]), xml).unwrap().is_none());
+    }
+
+    #[test]
+    fn zero_based_numbers_teams_and_players_from_0() {
+        let args = args(&["--zero-based"]);
+        let csv = render_csv(&args, &parse(&args, &attributes_xml(&[1], &[])));
+        assert!(csv.lines().nth(1).unwrap().starts_with("0,0,Hunter 00,"));
+    }
+
+    #[test]
+    fn missing_player_value_is_an_error() {
+        let xml = attributes_xml(&[1], &[]).replace("MissionBagPlayer_0_0_mmr", "Other");
+        assert!(matches!(
+            parse_match(&args(&[]), &xml),
+            Err(ExtractError::MissingAttribute { key }) if key == "MissionBagPlayer_0_0_mmr"
+        ));
+    }
+
+    #[test]
+    fn clear_stale_ignores_leftover_teams_and_players() {
+        let leftovers = [
+            ("MissionBagPlayer_4_0_blood_line_name", "Stale Hunter"),
+            ("MissionBagPlayer_4_0_profileid", "9999"),
+            ("MissionBagPlayer_1_3_profileid", "9998"),
+        ];
+        let xml = attributes_xml(&[2, 1], &leftovers);
+        let args = args(&["--clear-stale"]);
+
+        let attributes = read_attributes(&args, &xml).unwrap();
+        assert!(attributes
+            .iter()
+            .all(|item| !item.value.starts_with("Stale") && !item.value.starts_with("999")));
+        assert!(attributes
+            .iter()
+            .any(|item| item.name == "MissionBagNumTeams"));
+
+        let match_data = parse(&args, &xml);
+        assert_eq!(match_data.rows.len(), 3);
+        assert!(!render_csv(&args, &match_data).contains("Stale"));
+    }
+
+    #[test]
+    fn strict_rejects_player_attributes_of_undeclared_teams() {
+        let xml = attributes_xml(&[2, 1], &[("MissionBagPlayer_4_0_profileid", "9999")]);
+        assert!(matches!(
+            parse_match(&args(&["--strict"]), &xml),
+            Err(ExtractError::StaleTeam {
+                team: 4,
+                num_teams: 2
+            })
+        ));
+        assert!(parse_match(&args(&["--strict"]), &attributes_xml(&[2, 1], &[])).is_ok());
+    }
+
+    #[test]
+    fn json_keys_follow_headers_in_every_run() {
+        let args = args(&[]);
+        let xml = attributes_xml(&[3, 2], &[]);
+        let json = render_ndjson(&args, &parse(&args, &xml));
+        assert_eq!(json, render_ndjson(&args, &parse(&args, &xml)));
+        assert_eq!(
+            render_json(&args, &parse(&args, &xml)),
+            render_json(&args, &parse(&args, &xml))
+        );
+
+        let expected: Vec<&str> = ["team", "player"].iter().chain(HEADERS).copied().collect();
+        for line in json.lines() {
+            let positions: Vec<usize> = expected
+                .iter()
+                .map(|key| line.find(&format!("\"{key}\":")).unwrap())
+                .collect();
+            assert!(positions.windows(2).all(|pair| pair[0] < pair[1]), "{line}");
+        }
+    }
+
+    #[test]
+    fn player_slot_splits_every_header_suffix() {
+        for header in HEADERS {
+            let name = format!("MissionBagPlayer_12_3_{header}");
+            assert_eq!(player_slot(&name), Some((12, 3, *header)));
+        }
+        for suffix in [
+            "proximitytome_close",
+            "tooltip_2_kills",
+            "bounty_2",
+            "stat10",
+        ] {
+            let name = format!("MissionBagPlayer_0_1_{suffix}");
+            assert_eq!(player_slot(&name), Some((0, 1, suffix)));
+        }
+    }
+
+    #[test]
+    fn player_slot_rejects_other_names() {
+        for name in [
+            "MissionBagPlayer_0_1_",
+            "MissionBagPlayer_0_1",
+            "MissionBagPlayer_x_1_mmr",
+            "MissionBagPlayer_0_-1_mmr",
+            "MissionBagTeam_0_numplayers",
+            "XMissionBagPlayer_0_1_mmr",
+        ] {
+            assert_eq!(player_slot(name), None, "{name}");
+        }
+    }
+
+    /// The value of an attribute read from an 'attributes.xml'
+    fn read_value(xml: &str, name: &str) -> Option<String> {
+        read_attributes(&args(&[]), xml)
+            .unwrap()
+            .into_iter()
+            .find(|item| item.name == name)
+            .map(|item| item.value)
+    }
+
+    #[test]
+    fn reads_self_closing_attr_elements() {
+        let xml = r#"<Attributes><Attr name="a" value="1"/><Attr name="b" value=""/></Attributes>"#;
+        assert_eq!(read_value(xml, "a").as_deref(), Some("1"));
+        assert_eq!(read_value(xml, "b").as_deref(), Some(""));
+    }
+
+    #[test]
+    fn reads_attr_elements_with_text_content() {
+        let xml = r#"<Attributes><Attr name="a">1</Attr><Attr name="b"></Attr></Attributes>"#;
+        assert_eq!(read_value(xml, "a").as_deref(), Some("1"));
+        assert_eq!(read_value(xml, "b").as_deref(), Some(""));
+    }
+
+    #[test]
+    fn reads_reordered_and_padded_attr_elements() {
+        let xml = "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<Attributes>\n  <Attr  value='1'\n\
+                   \tname = \"a\" />\n  <Attr value=\"2\" name=\"b\"></Attr>\n</Attributes>\n";
+        assert_eq!(read_value(xml, "a").as_deref(), Some("1"));
+        assert_eq!(read_value(xml, "b").as_deref(), Some("2"));
+    }
+
+    #[test]
+    fn parses_a_match_from_mixed_attr_elements() {
+        let xml = attributes_xml(&[1], &[])
+            .replace(
+                r#"<Attr name="MissionBagNumTeams" value="1"/>"#,
+                r#"<Attr name="MissionBagNumTeams">1</Attr>"#,
+            )
+            .replace(
+                r#"<Attr name="MissionBagPlayer_0_0_mmr" value="2000"/>"#,
+                r#"<Attr value="2000"  name="MissionBagPlayer_0_0_mmr" />"#,
+            );
+        let args = args(&[]);
+        let match_data = parse(&args, &xml);
+        assert_eq!(match_data.value(&match_data.rows[0], "mmr"), Some("2000"));
+    }
+
+    #[test]
+    fn clamp_negatives_applies_to_counters_only() {
+        let xml = attributes_xml(&[2], &[])
+            .replace(
+                r#""MissionBagPlayer_0_0_killedbyme" value="0""#,
+                r#""MissionBagPlayer_0_0_killedbyme" value="-1""#,
+            )
+            .replace(
+                r#""MissionBagPlayer_0_1_mmr" value="2010""#,
+                r#""MissionBagPlayer_0_1_mmr" value="-1""#,
+            );
+
+        let raw = args(&[]);
+        let match_data = parse(&raw, &xml);
+        assert_eq!(
+            match_data.value(&match_data.rows[0], "killedbyme"),
+            Some("-1")
+        );
+
+        for (clamp, clamped) in [("zero", "0"), ("empty", "")] {
+            let args = args(&[&format!("--clamp-negatives={clamp}")]);
+            let match_data = parse(&args, &xml);
+            assert_eq!(
+                match_data.value(&match_data.rows[0], "killedbyme"),
+                Some(clamped)
+            );
+            assert_eq!(match_data.value(&match_data.rows[1], "mmr"), Some("-1"));
+        }
+    }
+
+    #[test]
+    fn csv_byte_layout() {
+        let xml = attributes_xml(&[1], &[]);
+        let header = format!("Team,Player,{}", HEADERS.join(","));
+        let row = "1,1,Hunter 00,2000,0,0,0,0,0,0,0,0,0,0,0,0,0,0,1000";
+
+        let args_default = args(&[]);
+        let csv = render_csv(&args_default, &parse(&args_default, &xml));
+        assert_eq!(csv, format!("{header}\n{row}"));
+
+        let args_newline = args(&["--trailing-newline"]);
+        let csv = render_csv(&args_newline, &parse(&args_newline, &xml));
+        assert_eq!(csv, format!("{header}\n{row}\n"));
+
+        let args_bom = args(&["--bom", "--embed-version", "--trailing-newline"]);
+        let csv = render_csv(&args_bom, &parse(&args_bom, &xml));
+        let version = format!("# {} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+        assert_eq!(csv, format!("\u{feff}{version}\n{header}\n{row}\n"));
+    }
+
+    #[test]
+    fn csv_has_one_header_row_for_any_team_sizes() {
+        let args = args(&[]);
+        let header = format!("Team,Player,{}", HEADERS.join(","));
+        for team_sizes in [
+            &[1, 1, 1][..],
+            &[2, 2],
+            &[3, 3, 3],
+            &[3, 1, 2, 0, 1],
+            &[4, 5],
+        ] {
+            let csv = render_csv(&args, &parse(&args, &attributes_xml(team_sizes, &[])));
+            let lines: Vec<&str> = csv.lines().collect();
+            assert_eq!(lines[0], header);
+            assert_eq!(lines.iter().filter(|&&l| l == header).count(), 1);
+            assert_eq!(lines.len() - 1, team_sizes.iter().sum::<u32>() as usize);
+        }
+    }
+
+    /// Team number of each row as written to CSV
+    fn csv_teams(csv: &str) -> Vec<String> {
+        csv.lines()
+            .skip(1)
+            .map(|line| line.split(',').next().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn empty_teams_are_kept_by_default() {
+        let args = args(&[]);
+        let match_data = parse(&args, &attributes_xml(&[2, 0, 1], &[]));
+        assert_eq!(match_data.team_sizes, vec![2, 0, 1]);
+        assert_eq!(csv_teams(&render_csv(&args, &match_data)), ["1", "1", "3"]);
+    }
+
+    #[test]
+    fn compact_empty_teams_keeps_team_numbers() {
+        let args = args(&["--compact-empty-teams"]);
+        let match_data = parse(&args, &attributes_xml(&[2, 0, 1], &[]));
+        assert_eq!(match_data.team_sizes, vec![2, 1]);
+        assert!(match_data
+            .rows
+            .iter()
+            .all(|row| (row.team as usize) < match_data.team_sizes.len()));
+        assert_eq!(csv_teams(&render_csv(&args, &match_data)), ["1", "1", "3"]);
+        assert!(render_json(&args, &match_data).contains("\"team\": 3"));
+    }
+
+    #[test]
+    fn compact_empty_teams_with_renumber_numbers_teams_consecutively() {
+        let args = args(&["--compact-empty-teams", "--renumber"]);
+        let match_data = parse(&args, &attributes_xml(&[2, 0, 1], &[]));
+        assert_eq!(match_data.team_sizes, vec![2, 1]);
+        assert_eq!(csv_teams(&render_csv(&args, &match_data)), ["1", "1", "2"]);
+    }
+
+    #[test]
+    fn xml_output_reads_back_as_the_same_match() {
+        for options in [
+            &[][..],
+            &["--compact-empty-teams"],
+            &["--compact-empty-teams", "--renumber"],
+            &["--zero-based"],
+        ] {
+            let args = args(options);
+            let original = parse(&args, &attributes_xml(&[2, 0, 1, 3], &[]));
+            let round_trip = parse(&args, &render_xml(&args, &original));
+            assert_eq!(round_trip.team_sizes, original.team_sizes, "{options:?}");
+            let values = |m: &Match| -> Vec<(u32, u32, Vec<String>)> {
+                m.rows
+                    .iter()
+                    .map(|row| (row.team, row.player, row.values.clone()))
+                    .collect()
+            };
+            assert_eq!(values(&round_trip), values(&original), "{options:?}");
+        }
+    }
+
+    #[test]
+    fn anonymize_tokens_depend_only_on_salt_and_id() {
+        assert_eq!(anonymize_token(7, "1001"), anonymize_token(7, "1001"));
+        assert_ne!(anonymize_token(7, "1001"), anonymize_token(7, "1002"));
+        assert_ne!(anonymize_token(7, "1001"), anonymize_token(8, "1001"));
+        assert_eq!(anonymize_token(0, "").len(), 16);
+    }
+
+    #[test]
+    fn raw_attributes_are_kept_for_a_raw_output_file() {
+        let xml = attributes_xml(&[1], &[("MissionBagMode", "a,b")]);
+        for options in [
+            &["--format", "csv,raw"][..],
+            &["--output-file", "match.raw"],
+        ] {
+            let args = args(options);
+            let raw = render_raw(&parse(&args, &xml));
+            assert!(
+                raw.starts_with("name,value\nMissionBagNumTeams,1\n"),
+                "{options:?}"
+            );
+            assert!(raw.ends_with("\nMissionBagMode,\"a,b\""), "{options:?}");
+        }
+        assert!(parse(&args(&[]), &xml).attributes.is_empty());
+    }
+
+    #[test]
+    fn report_shows_my_kda_on_my_line() {
+        let args = args(&["--my-profile-id", "1000", "--precision", "2"]);
+        let xml = attributes_xml(
+            &[1, 1],
+            &[
+                ("MissionBagPlayer_1_0_killedbyme", "2"),
+                ("MissionBagPlayer_1_0_killedme", "1"),
+            ],
+        );
+        let report = render_report(&args, &parse(&args, &xml));
+        let mine = report.lines().find(|l| l.starts_with('>')).unwrap();
+        assert!(mine.ends_with("KDA 2.00"), "{mine}");
+        assert!(report.contains("K/D vs your team 1/2"));
+    }
+}
@@ -0,0 +1,69 @@
+use crate::archive::{saved_csv_files, SavedMatch};
+use crate::progress::Progress;
+use crate::{file_timestamp, Args, TIMESTAMP_FORMAT};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Combines every timestamped CSV file in the output directory into a single file at
+/// `merge_path`, in chronological order with a 'match_timestamp' column taken from each file
+/// name
+pub fn merge_csv_files(args: &Args, output_dir_path: &Path, merge_path: &Path) -> io::Result<()> {
+    let mut files = saved_csv_files(args, output_dir_path)?;
+    files.retain(|p| p.file_name() != merge_path.file_name());
+
+    // Columns of every merged file, in the order they were first seen
+    let mut columns: Vec<String> = Vec::new();
+    let mut matches = Vec::new();
+    let mut progress = Progress::new("Merging", files.len() as u64);
+    for path in files {
+        progress.inc();
+        let timestamp = match file_timestamp(&path) {
+            Some(timestamp) => timestamp.format(TIMESTAMP_FORMAT).to_string(),
+            None => {
+                progress.println(&format!(
+                    "Warning: skipping '{}', its name is not a timestamp",
+                    path.to_string_lossy()
+                ));
+                continue;
+            }
+        };
+        match SavedMatch::read(&path) {
+            Ok(saved) => {
+                for column in &saved.columns {
+                    if column != "match_timestamp" && !columns.contains(column) {
+                        columns.push(column.clone());
+                    }
+                }
+                matches.push((timestamp, saved));
+            }
+            Err(reason) => progress.println(&format!(
+                "Warning: skipping malformed CSV file '{}': {reason}",
+                path.to_string_lossy()
+            )),
+        }
+    }
+    progress.finish();
+
+    let mut csv = format!("match_timestamp,{}", columns.join(","));
+    for (timestamp, saved) in &matches {
+        for row in &saved.rows {
+            csv.push_str(&format!("\n{timestamp}"));
+            for column in &columns {
+                match saved.value(row, column).unwrap_or_default() {
+                    v if v.contains(',') => csv.push_str(&format!(",\"{v}\"")),
+                    v => csv.push_str(&format!(",{v}")),
+                }
+            }
+        }
+    }
+
+    fs::write(merge_path, csv)?;
+    println!(
+        "Merged {} files into '{}'",
+        matches.len(),
+        merge_path.to_string_lossy()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use indicatif::{ProgressBar, ProgressStyle};
+use std::io::IsTerminal;
+
+/// Progress through a batch of files, shown as a bar on a terminal or as a line every 10%
+/// otherwise, e.g. when logging to a file
+pub struct Progress {
+    bar: Option<ProgressBar>,
+    message: &'static str,
+    total: u64,
+    done: u64,
+}
+
+impl Progress {
+    pub fn new(message: &'static str, total: u64) -> Progress {
+        let bar = std::io::stderr().is_terminal().then(|| {
+            let bar = ProgressBar::new(total);
+            bar.set_style(
+                ProgressStyle::with_template("{msg} [{bar:40}] {pos}/{len}")
+                    .unwrap()
+                    .progress_chars("=> "),
+            );
+            bar.set_message(message);
+            bar
+        });
+        Progress {
+            bar,
+            message,
+            total,
+            done: 0,
+        }
+    }
+
+    /// Prints a line without breaking up the bar
+    pub fn println(&self, line: &str) {
+        match &self.bar {
+            Some(bar) => bar.println(line),
+            None => console!("{line}"),
+        }
+    }
+
+    /// Marks one more file as done
+    pub fn inc(&mut self) {
+        self.done += 1;
+        match &self.bar {
+            Some(bar) => bar.inc(1),
+            None => {
+                let percent = self.done * 100 / self.total.max(1);
+                let previous = (self.done - 1) * 100 / self.total.max(1);
+                if percent / 10 > previous / 10 {
+                    console!(
+                        "{}: {percent}% ({}/{})",
+                        self.message,
+                        self.done,
+                        self.total
+                    );
+                }
+            }
+        }
+    }
+
+    pub fn finish(self) {
+        if let Some(bar) = self.bar {
+            bar.finish_and_clear();
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use crate::archive::{saved_csv_files, SavedMatch};
+use crate::derived::recompute_derived_columns;
+use crate::progress::Progress;
+use crate::{render_csv, Args, Match};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Rewrites every CSV file saved in the output directory with the derived columns enabled by
+/// `args` computed from its raw columns, first copying each to '<file>.bak' unless an earlier
+/// run already did
+pub fn recompute_saved_files(args: &Args, output_dir_path: &Path) -> io::Result<()> {
+    let files = saved_csv_files(args, output_dir_path)?;
+    let mut rewritten = 0;
+    let mut progress = Progress::new("Recomputing", files.len() as u64);
+    for path in files {
+        progress.inc();
+        let mut saved = match SavedMatch::read(&path) {
+            Ok(saved) => saved,
+            Err(reason) => {
+                progress.println(&format!(
+                    "Warning: skipping malformed CSV file '{}': {reason}",
+                    path.to_string_lossy()
+                ));
+                continue;
+            }
+        };
+
+        // Values written as '--na-string' count as missing
+        if let Some(na) = &args.na_string {
+            for value in saved.rows.iter_mut().flatten() {
+                if value == na {
+                    value.clear();
+                }
+            }
+        }
+
+        let Some(mut match_data) = Match::from_saved(args, &saved) else {
+            progress.println(&format!(
+                "Warning: skipping '{}', it has an invalid team or player number",
+                path.to_string_lossy()
+            ));
+            continue;
+        };
+        recompute_derived_columns(args, &mut match_data);
+
+        // Files keep the layout they were saved with, whatever the options of this run
+        let mut file_args = args.clone();
+        file_args.include_timestamp_column = saved.columns.iter().any(|c| c == "match_timestamp");
+        file_args.with_descriptions = saved.has_descriptions;
+        file_args.bom = false;
+        file_args.embed_version = false;
+        let mut csv = String::new();
+        if saved.has_bom {
+            csv.push(crate::BOM);
+        }
+        for comment in &saved.comments {
+            csv.push_str(&format!("{comment}\n"));
+        }
+        csv.push_str(&render_csv(&file_args, &match_data));
+
+        // The backup of an earlier run is of the original file
+        let mut backup = path.clone().into_os_string();
+        backup.push(".bak");
+        if !Path::new(&backup).exists() {
+            fs::copy(&path, &backup)?;
+        }
+        fs::write(&path, csv)?;
+        rewritten += 1;
+    }
+    progress.finish();
+
+    println!("Recomputed derived columns in {rewritten} files");
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use crate::archive::{saved_csv_files, SavedMatch};
+use crate::{render_json, sink, spool_dir, sse, Args, Match};
+use std::error::Error;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+/// Sends every CSV file saved in the output directory to '--post-url' and any '--sse-port'
+/// clients as if it were a new match, oldest first, waiting '--replay-delay' seconds between
+/// them
+pub fn replay(args: &Args, output_dir_path: &Path) -> Result<(), Box<dyn Error>> {
+    if args.post_url.is_none() && args.sse_port.is_none() {
+        return Err("'--replay' needs '--post-url' or '--sse-port' to send matches to".into());
+    }
+
+    let mut replayed = 0;
+    for path in saved_csv_files(args, output_dir_path)? {
+        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+        let match_data = match SavedMatch::read(&path) {
+            Ok(saved) => match Match::from_saved(args, &saved) {
+                Some(match_data) => match_data,
+                None => {
+                    console!("Warning: skipping '{name}', it has an invalid team or player number");
+                    continue;
+                }
+            },
+            Err(reason) => {
+                console!("Warning: skipping malformed CSV file '{name}': {reason}");
+                continue;
+            }
+        };
+
+        if replayed > 0 {
+            thread::sleep(Duration::from_secs(args.replay_delay));
+        }
+
+        let json = render_json(args, &match_data);
+        if let Some(url) = &args.post_url {
+            let spool_dir = spool_dir(args, output_dir_path);
+            sink::post_match(url, spool_dir.as_deref(), &name, &json);
+        }
+        if args.sse_port.is_some() {
+            sse::broadcast(&json);
+        }
+        console!("Replayed '{name}'");
+        replayed += 1;
+    }
+
+    console!("Replayed {replayed} matches");
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use crate::archive::{saved_csv_files, SavedMatch};
+use crate::{file_timestamp, Args, TIMESTAMP_FORMAT};
+use chrono::{Duration, NaiveDateTime};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Consecutive matches played with no gap longer than '--session-gap-mins'
+struct Session {
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    matches: usize,
+
+    /// Your MMR in the first and last matches of the session that you were found in
+    mmr: Option<(i64, i64)>,
+}
+
+/// Groups the CSV files saved in the output directory into play sessions and writes one row
+/// per session to `sessions_path`, with the change in your MMR if '--my-profile-id' is set
+pub fn write_sessions(args: &Args, output_dir_path: &Path, sessions_path: &Path) -> io::Result<()> {
+    // Parts of a match split across files share its timestamp
+    let mut matches: BTreeMap<NaiveDateTime, Option<i64>> = BTreeMap::new();
+    for path in saved_csv_files(args, output_dir_path)? {
+        let Some(timestamp) = file_timestamp(&path) else {
+            println!(
+                "Warning: skipping '{}', its name is not a timestamp",
+                path.to_string_lossy()
+            );
+            continue;
+        };
+        let my_mmr = match SavedMatch::read(&path) {
+            Ok(saved) => saved
+                .rows
+                .iter()
+                .find(|row| {
+                    args.my_profile_id.is_some()
+                        && saved.value(row, "profileid") == args.my_profile_id.as_deref()
+                })
+                .and_then(|row| saved.value(row, "mmr")?.parse().ok()),
+            Err(reason) => {
+                println!(
+                    "Warning: skipping malformed CSV file '{}': {reason}",
+                    path.to_string_lossy()
+                );
+                continue;
+            }
+        };
+        let entry = matches.entry(timestamp).or_default();
+        *entry = entry.or(my_mmr);
+    }
+
+    let gap = Duration::minutes(args.session_gap_mins as i64);
+    let mut sessions: Vec<Session> = Vec::new();
+    for (timestamp, my_mmr) in matches {
+        match sessions.last_mut() {
+            Some(session) if timestamp - session.end <= gap => {
+                session.end = timestamp;
+                session.matches += 1;
+                if let Some(mmr) = my_mmr {
+                    session.mmr = Some((session.mmr.map_or(mmr, |(first, _)| first), mmr));
+                }
+            }
+            _ => sessions.push(Session {
+                start: timestamp,
+                end: timestamp,
+                matches: 1,
+                mmr: my_mmr.map(|mmr| (mmr, mmr)),
+            }),
+        }
+    }
+
+    let mut csv = "session,start,end,matches,mmr_change".to_string();
+    for (i, session) in sessions.iter().enumerate() {
+        csv.push_str(&format!(
+            "\n{},{},{},{},{}",
+            i + if args.zero_based { 0 } else { 1 },
+            session.start.format(TIMESTAMP_FORMAT),
+            session.end.format(TIMESTAMP_FORMAT),
+            session.matches,
+            session
+                .mmr
+                .map(|(first, last)| (last - first).to_string())
+                .unwrap_or_default()
+        ));
+    }
+
+    fs::write(sessions_path, csv)?;
+    println!(
+        "Wrote {} sessions to '{}'",
+        sessions.len(),
+        sessions_path.to_string_lossy()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use clap::parser::ValueSource;
+use clap::{ArgAction, ArgMatches, Command};
+use serde_json::{Map, Value};
+use std::error::Error;
+use std::ffi::OsString;
+use std::fs;
+use std::path::Path;
+
+/// Arguments that choose settings files rather than being settings themselves
+const NOT_SETTINGS: &[&str] = &["config", "export_config", "help", "version"];
+
+/// Whether an argument was given rather than left at its default
+fn is_given(matches: &ArgMatches, id: &str) -> bool {
+    matches!(
+        matches.value_source(id),
+        Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+    )
+}
+
+/// Writes every argument given on the command line or through an environment variable to
+/// `path` as a JSON object keyed by long argument name, for loading with '--config'
+pub fn export_settings(
+    command: &Command,
+    matches: &ArgMatches,
+    path: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let mut settings = Map::new();
+    for arg in command.get_arguments() {
+        let id = arg.get_id().as_str();
+        let Some(long) = arg.get_long() else {
+            continue;
+        };
+        if NOT_SETTINGS.contains(&id) || !is_given(matches, id) {
+            continue;
+        }
+
+        let values: Vec<Value> = matches
+            .get_raw(id)
+            .into_iter()
+            .flatten()
+            .map(|v| v.to_string_lossy().into())
+            .collect();
+        let value = match arg.get_action() {
+            ArgAction::SetTrue => Value::Bool(true),
+            ArgAction::Append => Value::Array(values),
+            _ => values.into_iter().next().unwrap_or(Value::Null),
+        };
+        settings.insert(long.to_string(), value);
+    }
+
+    fs::write(path, serde_json::to_string_pretty(&settings)?)?;
+    println!("Settings exported to '{}'", path.to_string_lossy());
+    Ok(())
+}
+
+/// Parses the command line again with the settings in a file written by '--export-config'
+/// added, except for those given on the command line or through an environment variable
+pub fn with_settings_file(
+    command: Command,
+    matches: &ArgMatches,
+    path: &Path,
+) -> Result<ArgMatches, Box<dyn Error>> {
+    let contents = fs::read_to_string(path).map_err(|e| {
+        format!(
+            "Could not read settings file '{}': {e}",
+            path.to_string_lossy()
+        )
+    })?;
+    let settings: Map<String, Value> = serde_json::from_str(&contents)
+        .map_err(|e| format!("Invalid settings file '{}': {e}", path.to_string_lossy()))?;
+
+    let mut file_args: Vec<OsString> = Vec::new();
+    for (long, value) in &settings {
+        let arg = command
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(long.as_str()))
+            .ok_or_else(|| format!("Unknown setting '{long}' in '{}'", path.to_string_lossy()))?;
+        let id = arg.get_id().as_str();
+        if NOT_SETTINGS.contains(&id) || is_given(matches, id) {
+            continue;
+        }
+
+        let values = match value {
+            Value::Bool(true) => vec![None],
+            Value::Bool(false) | Value::Null => Vec::new(),
+            Value::Array(values) => values.iter().map(|v| Some(setting_text(v))).collect(),
+            value => vec![Some(setting_text(value))],
+        };
+        for value in values {
+            file_args.push(match value {
+                Some(value) => format!("--{long}={value}").into(),
+                None => format!("--{long}").into(),
+            });
+        }
+    }
+
+    let mut args = std::env::args_os();
+    let program = args.next().unwrap_or_default();
+    let args = [program].into_iter().chain(file_args).chain(args);
+    Ok(command.try_get_matches_from(args)?)
+}
+
+/// A setting's value as it would be given on the command line
+fn setting_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
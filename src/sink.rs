@@ -0,0 +1,55 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+fn post(url: &str, body: &str) -> Result<(), Box<dyn Error>> {
+    ureq::post(url)
+        .set("Content-Type", "application/json")
+        .send_string(body)?;
+    Ok(())
+}
+
+/// Posts a match to `url`, saving it as `<name>.json` in `spool_dir`, if any, to be retried
+/// later if the request fails
+pub fn post_match(url: &str, spool_dir: Option<&Path>, name: &str, body: &str) {
+    match (post(url, body), spool_dir) {
+        (Ok(()), spool_dir) => {
+            console!("Posted match to '{url}'");
+            if let Some(spool_dir) = spool_dir {
+                retry_spooled(url, spool_dir);
+            }
+        }
+        (Err(e), None) => console!("Could not post match to '{url}': {e}"),
+        (Err(e), Some(spool_dir)) => {
+            console!("Could not post match to '{url}', spooling for retry: {e}");
+            let result = fs::create_dir_all(spool_dir)
+                .and_then(|_| fs::write(spool_dir.join(format!("{name}.json")), body));
+            if let Err(e) = result {
+                console!("Could not spool match: {e}");
+            }
+        }
+    }
+}
+
+/// Posts any spooled matches to `url` in the order they were saved, stopping at the first
+/// failure
+pub fn retry_spooled(url: &str, spool_dir: &Path) {
+    let mut spooled: Vec<_> = match fs::read_dir(spool_dir) {
+        Ok(entries) => entries.flatten().map(|de| de.path()).collect(),
+        Err(_) => return,
+    };
+    spooled.sort();
+
+    for path in spooled {
+        let body = match fs::read_to_string(&path) {
+            Ok(body) => body,
+            Err(_) => continue,
+        };
+        if let Err(e) = post(url, &body) {
+            console!("Could not post spooled match to '{url}': {e}");
+            return;
+        }
+        console!("Posted spooled match '{}'", path.to_string_lossy());
+        let _ = fs::remove_file(&path);
+    }
+}
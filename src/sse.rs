@@ -0,0 +1,54 @@
+use std::error::Error;
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+
+/// Connections of every client listening for matches
+static CLIENTS: OnceLock<Mutex<Vec<Box<dyn Write + Send>>>> = OnceLock::new();
+
+/// Starts serving Server-Sent Events on `port` of localhost, to every path
+pub fn start(port: u16) -> Result<(), Box<dyn Error>> {
+    let server = tiny_http::Server::http(("127.0.0.1", port))
+        .map_err(|e| format!("Could not serve events on port {port}: {e}"))?;
+    CLIENTS.get_or_init(Default::default);
+    console!("Serving matches as Server-Sent Events at 'http://127.0.0.1:{port}/'");
+
+    std::thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let mut writer = request.into_writer();
+            let result = writer
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\n\
+                    Content-Type: text/event-stream\r\n\
+                    Cache-Control: no-cache\r\n\
+                    Access-Control-Allow-Origin: *\r\n\r\n",
+                )
+                .and_then(|_| writer.flush());
+            if result.is_ok() {
+                CLIENTS.get().unwrap().lock().unwrap().push(writer);
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Sends a 'match' event to every connected client, dropping any that have disconnected
+pub fn broadcast(data: &str) {
+    let clients = match CLIENTS.get() {
+        Some(clients) => clients,
+        None => return,
+    };
+
+    let mut event = String::from("event: match\n");
+    for line in data.lines() {
+        event.push_str(&format!("data: {line}\n"));
+    }
+    event.push('\n');
+
+    clients.lock().unwrap().retain_mut(|client| {
+        client
+            .write_all(event.as_bytes())
+            .and_then(|_| client.flush())
+            .is_ok()
+    });
+}
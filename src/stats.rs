@@ -0,0 +1,103 @@
+use crate::derived::{number, outcome};
+use crate::{Args, Match};
+use rusqlite::{params, Connection};
+use std::path::Path;
+
+/// Running totals kept in the 'stats' table that are printed as they are, along with the
+/// sum of each lobby's average MMR
+const TOTALS: &[(&str, &str)] = &[
+    ("matches", "Matches"),
+    ("my_matches", "Matches with '--my-profile-id'"),
+    ("extractions", "Extractions"),
+    ("kills", "Kills"),
+];
+
+fn open(path: &Path) -> rusqlite::Result<Connection> {
+    let db = Connection::open(path)?;
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS stats (name TEXT PRIMARY KEY, value REAL NOT NULL)",
+        [],
+    )?;
+    Ok(db)
+}
+
+/// Adds a new match to the running totals in the database at `path`
+pub fn record_match(args: &Args, path: &Path, match_data: &Match) -> rusqlite::Result<()> {
+    let values = |column| {
+        match_data
+            .rows
+            .iter()
+            .map(move |row| number(match_data.value(row, column)))
+    };
+    let mmrs: Vec<i64> = values("mmr").collect();
+    let mine = match_data.rows.iter().find(|row| {
+        args.my_profile_id.is_some()
+            && match_data.value(row, "profileid") == args.my_profile_id.as_deref()
+    });
+    let extracted = mine.is_some_and(|row| {
+        outcome(|column| match_data.value(row, column)).starts_with("extracted")
+    });
+
+    let increments = [
+        ("matches", 1.0),
+        ("my_matches", if mine.is_some() { 1.0 } else { 0.0 }),
+        ("extractions", if extracted { 1.0 } else { 0.0 }),
+        (
+            "lobby_mmr",
+            mmrs.iter().sum::<i64>() as f64 / mmrs.len().max(1) as f64,
+        ),
+        ("kills", values("killedbyme").sum::<i64>() as f64),
+    ];
+
+    let mut db = open(path)?;
+    let tx = db.transaction()?;
+    for (name, increment) in increments {
+        tx.execute(
+            "INSERT INTO stats (name, value) VALUES (?1, ?2)
+             ON CONFLICT (name) DO UPDATE SET value = value + ?2",
+            params![name, increment],
+        )?;
+    }
+    tx.commit()
+}
+
+/// Prints the running totals in the database at `path`, along with the averages per match
+pub fn print_stats(args: &Args, path: &Path) -> rusqlite::Result<()> {
+    let db = open(path)?;
+    let total = |name: &str| -> rusqlite::Result<f64> {
+        match db.query_row("SELECT value FROM stats WHERE name = ?1", [name], |r| {
+            r.get(0)
+        }) {
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(0.0),
+            result => result,
+        }
+    };
+
+    for (name, label) in TOTALS {
+        println!("{label}: {}", total(name)?);
+    }
+
+    let matches = total("matches")?;
+    let my_matches = total("my_matches")?;
+    if matches > 0.0 {
+        println!(
+            "Average lobby MMR: {:.*}",
+            args.precision,
+            total("lobby_mmr")? / matches
+        );
+        println!(
+            "Kills per match: {:.*}",
+            args.precision,
+            total("kills")? / matches
+        );
+    }
+    if my_matches > 0.0 {
+        println!(
+            "Extraction rate: {:.*}%",
+            args.precision,
+            100.0 * total("extractions")? / my_matches
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,20 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Phases of the current extraction and how long each took, for '--timings'
+static PHASES: Mutex<Vec<(&'static str, Duration)>> = Mutex::new(Vec::new());
+
+/// Records how long a phase of the current extraction has taken since `start`
+pub fn record(phase: &'static str, start: Instant) {
+    PHASES.lock().unwrap().push((phase, start.elapsed()));
+}
+
+/// Clears the recorded phases, printing them first if `print` is set
+pub fn finish(print: bool) {
+    let phases = std::mem::take(&mut *PHASES.lock().unwrap());
+    if print {
+        for (phase, duration) in phases {
+            console!("{phase}: {:.3} ms", duration.as_secs_f64() * 1000.0);
+        }
+    }
+}